@@ -0,0 +1,242 @@
+use crate::{CapSolverTask, TaskId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// How a solution may be served from a [`SolutionCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub expires_at: SystemTime,
+    /// Reusable solutions are served to every solve of the same task until they
+    /// expire, single-use ones are handed out once.
+    pub reusable: bool,
+}
+
+impl CachePolicy {
+    pub fn single_use(expires_at: SystemTime) -> Self {
+        Self {
+            expires_at,
+            reusable: false,
+        }
+    }
+
+    pub fn reusable(expires_at: SystemTime) -> Self {
+        Self {
+            expires_at,
+            reusable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub task_id: TaskId,
+    pub solution: Value,
+    pub expires_at: SystemTime,
+    pub reusable: bool,
+}
+
+/// Where a [`SolutionCache`] keeps its entries, one per key.
+///
+/// Calls are made from tokio's blocking thread pool, so backends may block.
+pub trait CacheBackend: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<CacheEntry>, String>;
+
+    fn insert(&self, key: &str, entry: CacheEntry) -> Result<(), String>;
+
+    fn remove(&self, key: &str) -> Result<Option<CacheEntry>, String>;
+}
+
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn insert(&self, key: &str, entry: CacheEntry) -> Result<(), String> {
+        let now = SystemTime::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        // Expired entries are otherwise only dropped when their key is looked up
+        entries.retain(|_, e| e.expires_at > now);
+        entries.insert(key.to_string(), entry);
+
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        Ok(self.entries.lock().unwrap().remove(key))
+    }
+}
+
+/// Keeps entries in a JSON file, so they survive restarts.
+///
+/// The file isn't locked, only one process should use it at a time.
+pub struct FileCache {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> Result<HashMap<String, CacheEntry>, String> {
+        match fs::read(&self.path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn save(&self, mut entries: HashMap<String, CacheEntry>) -> Result<(), String> {
+        let now = SystemTime::now();
+        entries.retain(|_, e| e.expires_at > now);
+
+        let data = serde_json::to_vec(&entries).map_err(|e| e.to_string())?;
+        let tmp = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+
+        fs::write(&tmp, data).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &self.path).map_err(|e| e.to_string())
+    }
+}
+
+impl CacheBackend for FileCache {
+    fn get(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        let _lock = self.lock.lock().unwrap();
+
+        Ok(self.load()?.remove(key))
+    }
+
+    fn insert(&self, key: &str, entry: CacheEntry) -> Result<(), String> {
+        let _lock = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+
+        entries.insert(key.to_string(), entry);
+        self.save(entries)
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        let _lock = self.lock.lock().unwrap();
+        let mut entries = self.load()?;
+        let entry = entries.remove(key);
+
+        if entry.is_some() {
+            self.save(entries)?;
+        }
+
+        Ok(entry)
+    }
+}
+
+/// Solutions keyed by the task they solve, see [`CapSolverTask::cache_policy`].
+#[derive(Clone)]
+pub struct SolutionCache {
+    backend: Arc<dyn CacheBackend>,
+    margin: Duration,
+}
+
+impl SolutionCache {
+    pub fn new(backend: impl CacheBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            margin: Duration::from_secs(10),
+        }
+    }
+
+    pub fn memory() -> Self {
+        Self::new(MemoryCache::default())
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::new(FileCache::new(path))
+    }
+
+    /// Entries are dropped this long before they expire, leaving time to use
+    /// them. Defaults to 10 seconds.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// A SHA-256 of the task, so proxy credentials and other task fields never
+    /// reach the backend.
+    pub(crate) fn key<T: CapSolverTask>(task: &T) -> Result<String, String> {
+        task.validate()?;
+
+        // Object keys are sorted, so equal tasks always give the same key
+        let digest = Sha256::digest(task.task()?.to_string().as_bytes());
+
+        Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Looks up `key`, taking single-use entries out of the cache.
+    pub(crate) async fn get(&self, key: &str) -> Result<Option<CacheEntry>, String> {
+        let margin = self.margin;
+
+        self.blocking(key, move |backend, key| {
+            let entry = match backend.get(key)? {
+                Some(entry) if !entry.reusable => backend.remove(key)?,
+                entry => entry,
+            };
+
+            match entry {
+                Some(entry) if is_fresh(&entry, margin) => Ok(Some(entry)),
+                Some(_) => {
+                    backend.remove(key)?;
+                    Ok(None)
+                }
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Stores `entry` unless it's already too close to expiry, returning whether it was.
+    pub(crate) async fn insert(&self, key: &str, entry: CacheEntry) -> Result<bool, String> {
+        if !is_fresh(&entry, self.margin) {
+            return Ok(false);
+        }
+
+        self.blocking(key, move |backend, key| backend.insert(key, entry))
+            .await?;
+
+        Ok(true)
+    }
+
+    async fn blocking<R, F>(&self, key: &str, f: F) -> Result<R, String>
+    where
+        R: Send + 'static,
+        F: FnOnce(&dyn CacheBackend, &str) -> Result<R, String> + Send + 'static,
+    {
+        let backend = self.backend.clone();
+        let key = key.to_string();
+
+        tokio::task::spawn_blocking(move || f(&*backend, &key))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
+
+fn is_fresh(entry: &CacheEntry, margin: Duration) -> bool {
+    SystemTime::now() + margin < entry.expires_at
+}
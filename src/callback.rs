@@ -0,0 +1,219 @@
+use hyper::{
+    body::HttpBody,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use reqwest::Url;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// How long a pushed result is kept around when nobody is waiting for it yet.
+const UNCLAIMED_TTL: Duration = Duration::from_secs(600);
+/// Results kept around when nobody is waiting for them, the oldest go first.
+const MAX_UNCLAIMED: usize = 1000;
+/// Largest callback body accepted, results are a few KiB at most.
+const MAX_BODY_SIZE: usize = 256 * 1024;
+
+#[derive(Default)]
+struct Results {
+    waiting: HashMap<String, oneshot::Sender<Value>>,
+    unclaimed: HashMap<String, (Instant, Value)>,
+}
+
+impl Results {
+    fn deliver(&mut self, task_id: String, data: Value) {
+        let data = match self.waiting.remove(&task_id) {
+            Some(tx) => match tx.send(data) {
+                Ok(()) => return,
+                Err(data) => data,
+            },
+            None => data,
+        };
+
+        self.unclaimed
+            .retain(|_, (received, _)| received.elapsed() < UNCLAIMED_TTL);
+
+        if self.unclaimed.len() >= MAX_UNCLAIMED {
+            let oldest = self
+                .unclaimed
+                .iter()
+                .min_by_key(|(_, (received, _))| *received)
+                .map(|(task_id, _)| task_id.clone());
+
+            if let Some(task_id) = oldest {
+                self.unclaimed.remove(&task_id);
+            }
+        }
+
+        self.unclaimed.insert(task_id, (Instant::now(), data));
+    }
+}
+
+struct Shutdown(Option<oneshot::Sender<()>>);
+
+impl Drop for Shutdown {
+    fn drop(&mut self) {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// Embedded HTTP listener for results pushed by CapSolver to a `callbackUrl`.
+///
+/// Each receiver generates a secret that is added to the `callbackUrl` as the
+/// `token` query parameter, requests without it are rejected.
+///
+/// The listener stops once the last clone of the receiver is dropped.
+#[derive(Clone)]
+pub struct CallbackReceiver {
+    local_addr: SocketAddr,
+    secret: Arc<str>,
+    results: Arc<Mutex<Results>>,
+    _shutdown: Arc<Shutdown>,
+}
+
+impl CallbackReceiver {
+    /// Starts listening on `addr`. Must be called from within a tokio runtime.
+    pub fn bind(addr: SocketAddr) -> Result<Self, String> {
+        let results = Arc::new(Mutex::new(Results::default()));
+        let secret: Arc<str> = secret()?.into();
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let shared = (results.clone(), secret.clone());
+        let make_svc = make_service_fn(move |_| {
+            let (results, secret) = shared.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    handle(req, results.clone(), secret.clone())
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)
+            .map_err(|e| e.to_string())?
+            .serve(make_svc);
+        let local_addr = server.local_addr();
+
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = rx.await;
+        }));
+
+        Ok(Self {
+            local_addr,
+            secret,
+            results,
+            _shutdown: Arc::new(Shutdown(Some(tx))),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// `callback_url` with the secret CapSolver has to send back.
+    pub(crate) fn authorize(&self, callback_url: &Url) -> Url {
+        let mut url = callback_url.clone();
+        url.query_pairs_mut().append_pair("token", &self.secret);
+        url
+    }
+
+    /// Waits up to `timeout` for the result of `task_id` to be pushed.
+    pub(crate) async fn wait(&self, task_id: &str, timeout: Duration) -> Option<Value> {
+        let rx = {
+            let mut results = self.results.lock().unwrap();
+
+            if let Some((_, data)) = results.unclaimed.remove(task_id) {
+                return Some(data);
+            }
+
+            let (tx, rx) = oneshot::channel();
+            results.waiting.insert(task_id.to_string(), tx);
+            rx
+        };
+
+        let res = tokio::time::timeout(timeout, rx).await;
+        self.results.lock().unwrap().waiting.remove(task_id);
+
+        res.ok().and_then(Result::ok)
+    }
+}
+
+async fn handle(
+    req: Request<Body>,
+    results: Arc<Mutex<Results>>,
+    secret: Arc<str>,
+) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let token = Url::parse(&format!("http://localhost{}", req.uri()))
+        .ok()
+        .and_then(|u| {
+            u.query_pairs()
+                .find(|(k, _)| k == "token")
+                .map(|(_, v)| v.into_owned())
+        });
+
+    if !token.is_some_and(|t| constant_time_eq(t.as_bytes(), secret.as_bytes())) {
+        return Ok(status(StatusCode::FORBIDDEN));
+    }
+
+    let body = match read_body(req.into_body()).await {
+        Some(body) => body,
+        None => return Ok(status(StatusCode::PAYLOAD_TOO_LARGE)),
+    };
+
+    let data = match serde_json::from_slice::<Value>(&body) {
+        Ok(d) if d["taskId"].is_string() => d,
+        _ => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+
+    let task_id = data["taskId"].as_str().unwrap().to_string();
+    results.lock().unwrap().deliver(task_id, data);
+
+    Ok(status(StatusCode::OK))
+}
+
+/// Reads the body, giving up once it's larger than `MAX_BODY_SIZE`.
+async fn read_body(mut body: Body) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.ok()?;
+
+        if buf.len() + chunk.len() > MAX_BODY_SIZE {
+            return None;
+        }
+
+        buf.extend_from_slice(&chunk);
+    }
+
+    Some(buf)
+}
+
+fn secret() -> Result<String, String> {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = code;
+    res
+}
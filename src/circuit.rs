@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Share of failed requests in the window at which the circuit opens.
+    pub failure_rate: f64,
+    /// Requests needed in the window before the failure rate is considered.
+    pub min_requests: usize,
+    /// Number of most recent requests the failure rate is computed over.
+    pub window: usize,
+    /// How long the circuit stays open before probing the API again.
+    pub open_for: Duration,
+    /// Requests let through at once while half-open.
+    pub half_open_probes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            min_requests: 10,
+            window: 20,
+            open_for: Duration::from_secs(30),
+            half_open_probes: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+enum State {
+    Closed(VecDeque<bool>),
+    Open(Instant),
+    HalfOpen(usize),
+}
+
+/// Fails requests fast while the API keeps failing at the transport or 5xx level.
+pub(crate) struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::Closed(VecDeque::new())),
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        match &*self.state.lock().unwrap() {
+            State::Closed(_) => CircuitState::Closed,
+            State::Open(until) if Instant::now() >= *until => CircuitState::HalfOpen,
+            State::Open(_) => CircuitState::Open,
+            State::HalfOpen(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Checks whether a request may be sent, the outcome is reported through
+    /// the returned permit.
+    pub(crate) fn acquire(&self) -> Result<Permit<'_>, String> {
+        let mut state = self.state.lock().unwrap();
+
+        match &mut *state {
+            State::Closed(_) => {}
+            State::Open(until) if Instant::now() >= *until => *state = State::HalfOpen(1),
+            State::HalfOpen(in_flight) if *in_flight < self.config.half_open_probes => {
+                *in_flight += 1
+            }
+            _ => {
+                return Err(
+                    "ERROR_CIRCUIT_OPEN: CapSolver API is failing, not sending requests"
+                        .to_string(),
+                )
+            }
+        }
+
+        Ok(Permit {
+            breaker: self,
+            recorded: false,
+        })
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        let config = &self.config;
+
+        match &mut *state {
+            State::Closed(outcomes) => {
+                outcomes.push_back(success);
+
+                while outcomes.len() > config.window {
+                    outcomes.pop_front();
+                }
+
+                let failures = outcomes.iter().filter(|s| !**s).count();
+
+                if outcomes.len() >= config.min_requests
+                    && failures as f64 / outcomes.len() as f64 >= config.failure_rate
+                {
+                    *state = State::Open(Instant::now() + config.open_for);
+                }
+            }
+            State::HalfOpen(_) if success => *state = State::Closed(VecDeque::new()),
+            State::HalfOpen(_) => *state = State::Open(Instant::now() + config.open_for),
+            State::Open(_) => {}
+        }
+    }
+}
+
+pub(crate) struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    recorded: bool,
+}
+
+impl Permit<'_> {
+    pub(crate) fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(success);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        // The request was cancelled, free its probe slot without judging the API
+        if !self.recorded {
+            if let State::HalfOpen(in_flight) = &mut *self.breaker.state.lock().unwrap() {
+                *in_flight = in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
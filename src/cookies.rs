@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// A cookie forwarded to a reCAPTCHA task.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaskCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl TaskCookie {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: None,
+            path: None,
+        }
+    }
+}
+
+#[cfg(feature = "cookies")]
+impl TaskCookie {
+    /// Cookies from `jar` that would be sent to `website_url`.
+    ///
+    /// The jar only hands out names and values, so `domain` and `path` are left unset.
+    pub fn from_jar(jar: &reqwest::cookie::Jar, website_url: &str) -> Result<Vec<Self>, String> {
+        use reqwest::cookie::CookieStore;
+
+        let url = reqwest::Url::parse(website_url).map_err(|e| e.to_string())?;
+        let header = match jar.cookies(&url) {
+            Some(h) => h,
+            None => return Ok(Vec::new()),
+        };
+        let header = header.to_str().map_err(|e| e.to_string())?;
+
+        Ok(header
+            .split(';')
+            .filter_map(|c| c.trim().split_once('='))
+            .map(|(name, value)| Self::new(name, value))
+            .collect())
+    }
+}
+
+#[cfg(feature = "cookies")]
+impl From<&cookie::Cookie<'_>> for TaskCookie {
+    fn from(c: &cookie::Cookie<'_>) -> Self {
+        Self {
+            name: c.name().to_string(),
+            value: c.value().to_string(),
+            domain: c.domain().map(str::to_string),
+            path: c.path().map(str::to_string),
+        }
+    }
+}
+
+#[cfg(feature = "cookies")]
+impl From<cookie::Cookie<'_>> for TaskCookie {
+    fn from(c: cookie::Cookie<'_>) -> Self {
+        Self::from(&c)
+    }
+}
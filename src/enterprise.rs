@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// `enterprisePayload` of hCaptcha tasks.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HCaptchaEnterprisePayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rqdata: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sentry: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reportapi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assethost: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imghost: Option<String>,
+    /// Any other key, sent as is.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// `enterprisePayload` of reCAPTCHA Enterprise tasks.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReCaptchaEnterprisePayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s: Option<String>,
+    /// Any other key, sent as is.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
@@ -0,0 +1,112 @@
+use std::{fmt, str::FromStr};
+
+/// What kind of problem an [`ApiErrorCode`] points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The client key or IP isn't allowed to use the API.
+    Auth,
+    /// The account can't pay for the task.
+    Billing,
+    /// The task was rejected as sent, retrying it unchanged won't help.
+    Input,
+    /// CapSolver is overloaded or unavailable, retry later.
+    Transient,
+    /// The task was accepted but couldn't be solved, a new task may succeed.
+    SolveFailure,
+    /// The supplied proxy can't be used.
+    Proxy,
+    /// The circuit breaker is failing requests without sending them, retrying
+    /// before it closes won't help.
+    CircuitOpen,
+    Unknown,
+}
+
+impl ErrorCategory {
+    /// Whether creating the task again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Transient | Self::SolveFailure)
+    }
+}
+
+macro_rules! api_error_codes {
+    ($($variant:ident => $code:literal, $category:ident;)+) => {
+        /// Error codes returned by CapSolver as `errorCode`, plus this crate's
+        /// own `ERROR_CIRCUIT_OPEN`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum ApiErrorCode {
+            $($variant,)+
+            Unknown(String),
+        }
+
+        impl ApiErrorCode {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $code,)+
+                    Self::Unknown(code) => code,
+                }
+            }
+
+            pub fn category(&self) -> ErrorCategory {
+                match self {
+                    $(Self::$variant => ErrorCategory::$category,)+
+                    Self::Unknown(_) => ErrorCategory::Unknown,
+                }
+            }
+        }
+
+        impl FromStr for ApiErrorCode {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(match s {
+                    $($code => Self::$variant,)+
+                    _ => Self::Unknown(s.to_string()),
+                })
+            }
+        }
+    };
+}
+
+api_error_codes! {
+    ServiceUnavailable => "ERROR_SERVICE_UNAVALIABLE", Transient;
+    RateLimit => "ERROR_RATE_LIMIT", Transient;
+    TaskTimeout => "ERROR_TASK_TIMEOUT", Transient;
+    KeyDeniedAccess => "ERROR_KEY_DENIED_ACCESS", Auth;
+    KeyTempBlocked => "ERROR_KEY_TEMP_BLOCKED", Auth;
+    IpBanned => "ERROR_IP_BANNED", Auth;
+    ZeroBalance => "ERROR_ZERO_BALANCE", Billing;
+    SettlementFailed => "ERROR_SETTLEMENT_FAILED", Billing;
+    InvalidTaskData => "ERROR_INVALID_TASK_DATA", Input;
+    BadRequest => "ERROR_BAD_REQUEST", Input;
+    TaskIdInvalid => "ERROR_TASKID_INVALID", Input;
+    TaskNotSupported => "ERROR_TASK_NOT_SUPPORTED", Input;
+    UnknownQuestion => "ERROR_UNKNOWN_QUESTION", Input;
+    InvalidImage => "ERROR_INVALID_IMAGE", Input;
+    ParseImageFail => "ERROR_PARSE_IMAGE_FAIL", Input;
+    CaptchaSolveFailed => "ERROR_CAPTCHA_SOLVE_FAILED", SolveFailure;
+    ProxyBanned => "ERROR_PROXY_BANNED", Proxy;
+    // Not sent by the API, returned while the circuit breaker is open
+    CircuitOpen => "ERROR_CIRCUIT_OPEN", CircuitOpen;
+}
+
+impl ApiErrorCode {
+    /// Extracts the code from an error returned by this crate, which are
+    /// formatted as `"{errorCode}: {errorDescription}"`.
+    ///
+    /// Returns `None` for errors that didn't come from the API, e.g. transport errors.
+    pub fn from_error(err: &str) -> Option<Self> {
+        let (code, _) = err.split_once(':')?;
+
+        if !code.starts_with("ERROR_") {
+            return None;
+        }
+
+        code.parse().ok()
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{ApiErrorCode, CapSolver, CapSolverTask, Solution, TaskId, TaskMeta};
+use std::{
+    future::{poll_fn, Future},
+    sync::Mutex,
+    task::Poll,
+    time::Duration,
+};
+use tokio::time::{sleep, Instant};
+
+#[derive(Debug, Clone)]
+pub struct HedgeOptions {
+    /// How long to wait for the newest task before creating a duplicate.
+    pub extra_after: Duration,
+    /// Tasks created at most, including the first one.
+    pub max_parallel: usize,
+}
+
+impl Default for HedgeOptions {
+    fn default() -> Self {
+        Self {
+            extra_after: Duration::from_secs(10),
+            max_parallel: 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct HedgedSolution<S> {
+    pub solution: Solution<S>,
+    pub meta: TaskMeta,
+    /// Duplicates that were still running when the solution came in. They are
+    /// no longer polled but CapSolver may still solve and charge for them.
+    pub abandoned: Vec<TaskId>,
+    /// Duplicates whose `createTask` was still in flight when the solution came
+    /// in, CapSolver may have created and charged for them too.
+    pub abandoned_submits: usize,
+}
+
+impl<S> HedgedSolution<S> {
+    /// Estimated cost of the abandoned duplicates, priced like the winning task.
+    pub fn extra_cost(&self) -> Option<f64> {
+        let abandoned = self.abandoned.len() + self.abandoned_submits;
+
+        self.meta.cost.map(|c| c * abandoned as f64)
+    }
+
+    pub fn into_inner(self) -> Solution<S> {
+        self.solution
+    }
+}
+
+impl CapSolver {
+    /// Solves `task`, creating a duplicate whenever the newest task hasn't been
+    /// solved within `extra_after`, and returns the first solution.
+    ///
+    /// A task that fails with a retryable error starts the next duplicate right
+    /// away, any other error stops creating duplicates.
+    pub async fn solve_hedged<T: CapSolverTask>(
+        &self,
+        task: &T,
+        options: HedgeOptions,
+    ) -> Result<HedgedSolution<T::Solution>, String> {
+        let in_flight = Mutex::new(Vec::new());
+        let in_flight_ref = &in_flight;
+
+        let race = |n: usize| async move {
+            // Tracked from the start, a task may be created even if we stop waiting
+            in_flight_ref.lock().unwrap().push((n, None));
+
+            let pending = match self.submit(task).await {
+                Ok(pending) => pending,
+                Err(e) => {
+                    in_flight_ref.lock().unwrap().retain(|(i, _)| *i != n);
+                    return Err(e);
+                }
+            };
+            let task_id = pending.task_id().clone();
+
+            for (i, id) in in_flight_ref.lock().unwrap().iter_mut() {
+                if *i == n {
+                    *id = Some(task_id.clone());
+                }
+            }
+
+            let res = pending.wait_detailed(self).await;
+            in_flight_ref.lock().unwrap().retain(|(i, _)| *i != n);
+
+            res.map(|r| (task_id, r))
+        };
+
+        let mut max_parallel = options.max_parallel.max(1);
+        let mut racers = vec![Box::pin(race(0))];
+        let mut started = 1;
+        let mut next = Box::pin(sleep(options.extra_after));
+
+        loop {
+            // `None` when the next duplicate is due
+            let event = poll_fn(|cx| {
+                for (i, racer) in racers.iter_mut().enumerate() {
+                    if let Poll::Ready(res) = racer.as_mut().poll(cx) {
+                        return Poll::Ready(Some((i, res)));
+                    }
+                }
+
+                if started < max_parallel && next.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(None);
+                }
+
+                Poll::Pending
+            })
+            .await;
+
+            match event {
+                Some((_, Ok((task_id, res)))) => {
+                    // Dropping the other racers stops polling them
+                    drop(racers);
+
+                    let (abandoned, submits): (Vec<_>, Vec<_>) = in_flight
+                        .into_inner()
+                        .unwrap()
+                        .into_iter()
+                        .map(|(_, id)| id)
+                        .partition(Option::is_some);
+
+                    return Ok(HedgedSolution {
+                        solution: Solution {
+                            task_id,
+                            solution: res.solution,
+                        },
+                        meta: res.meta,
+                        abandoned: abandoned.into_iter().flatten().collect(),
+                        abandoned_submits: submits.len(),
+                    });
+                }
+                Some((i, Err(e))) => {
+                    drop(racers.remove(i));
+
+                    // Another task would fail the same way, only wait for the running ones
+                    let retryable =
+                        ApiErrorCode::from_error(&e).is_some_and(|c| c.category().is_retryable());
+
+                    if !retryable {
+                        max_parallel = started;
+                    }
+
+                    if started == max_parallel {
+                        if racers.is_empty() {
+                            return Err(e);
+                        }
+
+                        continue;
+                    }
+                }
+                None => {}
+            }
+
+            racers.push(Box::pin(race(started)));
+            started += 1;
+            next.as_mut().reset(Instant::now() + options.extra_after);
+        }
+    }
+}
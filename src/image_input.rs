@@ -0,0 +1,145 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::path::{Path, PathBuf};
+
+/// Largest decoded image accepted for upload.
+pub const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Bmp,
+}
+
+impl ImageFormat {
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0x89, b'P', b'N', b'G', ..] => Some(Self::Png),
+            [0xFF, 0xD8, 0xFF, ..] => Some(Self::Jpeg),
+            [b'G', b'I', b'F', b'8', ..] => Some(Self::Gif),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(Self::Webp),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            _ => None,
+        }
+    }
+}
+
+/// An image to upload to a recognition task.
+///
+/// Plain strings are treated as base64, optionally prefixed with a data URI.
+#[derive(Debug, Clone)]
+pub enum ImageInput {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+    Base64(String),
+    #[cfg(feature = "image")]
+    Image(image::DynamicImage),
+}
+
+impl ImageInput {
+    /// Returns the image as plain base64, ready to be sent to CapSolver.
+    pub async fn to_base64(&self) -> Result<String, String> {
+        match self {
+            Self::Path(p) => {
+                let bytes = tokio::fs::read(p)
+                    .await
+                    .map_err(|e| format!("{}: {}", p.display(), e))?;
+
+                encode(&bytes)
+            }
+            Self::Bytes(b) => encode(b),
+            Self::Base64(s) => {
+                let data = match s.trim().strip_prefix("data:") {
+                    Some(uri) => match uri.split_once(',') {
+                        Some((_, data)) => data,
+                        None => return Err("Invalid data URI".to_string()),
+                    },
+                    None => s.trim(),
+                };
+                let bytes = STANDARD
+                    .decode(data)
+                    .map_err(|e| format!("Invalid base64 image: {}", e))?;
+
+                validate(&bytes)?;
+
+                Ok(data.to_string())
+            }
+            #[cfg(feature = "image")]
+            Self::Image(img) => {
+                let mut bytes = std::io::Cursor::new(Vec::new());
+
+                img.write_to(&mut bytes, image::ImageFormat::Png)
+                    .map_err(|e| e.to_string())?;
+
+                encode(bytes.get_ref())
+            }
+        }
+    }
+}
+
+fn validate(bytes: &[u8]) -> Result<ImageFormat, String> {
+    if bytes.is_empty() {
+        return Err("Image is empty".to_string());
+    }
+
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return Err(format!(
+            "Image is {} bytes, the limit is {}",
+            bytes.len(),
+            MAX_IMAGE_SIZE
+        ));
+    }
+
+    ImageFormat::detect(bytes).ok_or_else(|| "Unsupported image format".to_string())
+}
+
+fn encode(bytes: &[u8]) -> Result<String, String> {
+    validate(bytes)?;
+
+    Ok(STANDARD.encode(bytes))
+}
+
+impl From<&Path> for ImageInput {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for ImageInput {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<&[u8]> for ImageInput {
+    fn from(bytes: &[u8]) -> Self {
+        Self::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for ImageInput {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<&str> for ImageInput {
+    fn from(base64: &str) -> Self {
+        Self::Base64(base64.to_string())
+    }
+}
+
+impl From<String> for ImageInput {
+    fn from(base64: String) -> Self {
+        Self::Base64(base64)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::DynamicImage> for ImageInput {
+    fn from(img: image::DynamicImage) -> Self {
+        Self::Image(img)
+    }
+}
@@ -1,18 +1,57 @@
+use circuit::CircuitBreaker;
 use reqwest::{Client, Url};
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
-use std::{collections::HashMap, time::Duration};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Deref,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::time::interval;
 
-const SUPPORTED_MODULES: [&'static str; 2] = ["common", "queueit"];
-const H_CAPTCHA_TYPES: [&'static str; 3] =
-    ["HCaptchaTask", "HCaptchaTaskProxyLess", "HCaptchaTurboTask"];
-const GEE_TEST_TYPES: [&'static str; 2] = ["GeeTestTask", "GeeTestTaskProxyLess"];
-const RE_CAPTCHA_V2_TYPES: [&'static str; 2] = ["ReCaptchaV2Task", "ReCaptchaV2TaskProxyLess"];
-const RE_CAPTCHA_V3_TYPES: [&'static str; 2] = ["ReCaptchaV3Task", "ReCaptchaV3TaskProxyLess"];
-const MT_CAPTCHA_TYPES: [&'static str; 2] = ["MtCaptchaTask", "MtCaptchaTaskProxyLess"];
-const AWS_WAF_TYPES: [&'static str; 2] = ["AwsWafTask", "AwsWafTaskProxyLess"];
-const CYBER_SI_ARA_TYPES: [&'static str; 2] = ["AwsWafTask", "AwsWafTaskProxyLess"];
+mod cache;
+#[cfg(feature = "callback")]
+mod callback;
+mod circuit;
+mod cookies;
+mod enterprise;
+mod error;
+mod hedge;
+mod image_input;
+mod pending;
+mod pool;
+mod proxy;
+mod retry;
+mod task;
+mod task_type;
+
+pub use cache::{CacheBackend, CacheEntry, CachePolicy, FileCache, MemoryCache, SolutionCache};
+#[cfg(feature = "callback")]
+pub use callback::CallbackReceiver;
+pub use circuit::{CircuitBreakerConfig, CircuitState};
+pub use cookies::TaskCookie;
+pub use enterprise::{HCaptchaEnterprisePayload, ReCaptchaEnterprisePayload};
+pub use error::{ApiErrorCode, ErrorCategory};
+pub use hedge::{HedgeOptions, HedgedSolution};
+pub use image_input::{ImageFormat, ImageInput, MAX_IMAGE_SIZE};
+pub use pending::PendingTask;
+pub use pool::{PoolOptions, PoolStats, TokenPool};
+pub use proxy::{Proxy, ProxyScheme};
+pub use retry::{accept_all, AttemptOutcome, RetryAttempt, RetryOptions, RetryOutcome};
+pub use task::{
+    AwsWafTask, CapSolverTask, CloudflareChallengeTask, GeeTestV3Task, GeeTestV4Task, HCaptchaTask,
+    ReCaptchaV2Task, ReCaptchaV3Task, TurnstileMetadata, TurnstileTask,
+};
+pub use task_type::{
+    AwsWafType, CyberSiAraType, FunCaptchaType, GeeTestType, HCaptchaType, MtCaptchaType,
+    ReCaptchaV2Type, ReCaptchaV3Type, TaskType, TurnstileType,
+};
+
+const SUPPORTED_MODULES: [&str; 2] = ["common", "queueit"];
 
 #[derive(Clone)]
 pub struct Config {
@@ -20,6 +59,12 @@ pub struct Config {
     api_url: Url,
     client: Client,
     interval: u64,
+    request_timeout: Option<Duration>,
+    callback_url: Option<Url>,
+    #[cfg(feature = "callback")]
+    callback: Option<(CallbackReceiver, Duration)>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    cache: Option<SolutionCache>,
 }
 
 impl Config {
@@ -34,9 +79,57 @@ impl Config {
             api_key,
             client,
             interval,
+            request_timeout: None,
+            callback_url: None,
+            #[cfg(feature = "callback")]
+            callback: None,
+            circuit_breaker: None,
+            cache: None,
         }
     }
 
+    /// Sets the `callbackUrl` CapSolver pushes task results to.
+    pub fn with_callback_url(mut self, callback_url: &str) -> Result<Self, String> {
+        self.callback_url = Some(Url::parse(callback_url).map_err(|e| e.to_string())?);
+
+        Ok(self)
+    }
+
+    /// Waits up to `timeout` for results pushed to `receiver` before falling back
+    /// to polling `getTaskResult`.
+    ///
+    /// The receiver only listens locally, `callback_url` must be the address at
+    /// which CapSolver can reach it.
+    #[cfg(feature = "callback")]
+    pub fn with_callback_receiver(mut self, receiver: CallbackReceiver, timeout: Duration) -> Self {
+        self.callback = Some((receiver, timeout));
+        self
+    }
+
+    /// Fails requests that take longer than `timeout` to complete, including
+    /// reading the response.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Stops sending requests for a while once too many of them fail at the
+    /// transport or 5xx level, shared by every clone of this config.
+    ///
+    /// Requests that hang only count as failures once they time out, see
+    /// [`Config::with_request_timeout`].
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(Arc::new(CircuitBreaker::new(config)));
+        self
+    }
+
+    /// Serves `solve` from `cache` when the task allows it, see
+    /// [`CapSolverTask::cache_policy`].
+    pub fn with_cache(mut self, cache: SolutionCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn from_env() -> Result<Config, String> {
         match option_env!("CAPSOLVER_CLIENT_KEY") {
             Some(s) => Ok(Config::new(s, None, None)),
@@ -44,43 +137,182 @@ impl Config {
         }
     }
 
+    /// A truncated SHA-256 of the client key, enough to tell keys apart without
+    /// revealing any part of them.
+    fn key_fingerprint(&self) -> String {
+        let digest = Sha256::digest(self.api_key.as_bytes());
+
+        digest[..16].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
     fn make_body(&self) -> Value {
         json!({
             "clientKey": self.api_key
         })
     }
 
-    async fn create_task(&self, body: Value) -> Result<Value, String> {
-        let res = self
+    fn task_body<T: CapSolverTask>(&self, task: &T) -> Result<Value, String> {
+        task.validate()?;
+
+        let mut body = self.make_body();
+        body["task"] = task.task()?;
+
+        Ok(body)
+    }
+
+    async fn create_task(&self, mut body: Value) -> Result<Value, String> {
+        if let Some(callback_url) = &self.callback_url {
+            #[cfg(feature = "callback")]
+            if let Some((receiver, _)) = &self.callback {
+                body["callbackUrl"] = json!(receiver.authorize(callback_url).as_str());
+                return self.post("createTask", &body).await;
+            }
+
+            body["callbackUrl"] = json!(callback_url.as_str());
+        }
+
+        self.post("createTask", &body).await
+    }
+
+    async fn post(&self, endpoint: &str, body: &Value) -> Result<Value, String> {
+        let permit = match &self.circuit_breaker {
+            Some(breaker) => Some(breaker.acquire()?),
+            None => None,
+        };
+
+        let mut req = self
             .client
-            .post(self.api_url.join("createTask").unwrap())
-            .json(&body)
-            .send()
-            .await;
+            .post(self.api_url.join(endpoint).unwrap())
+            .json(body);
 
-        match res {
-            Ok(o) => {
-                let data: Value = serde_json::from_str(o.text().await.unwrap().as_str()).unwrap();
-
-                if data["errorId"].as_i64().unwrap() != 0 {
-                    return Err(format!(
-                        "{}: {}",
-                        data["errorCode"].as_str().unwrap(),
-                        data["errorDescription"].as_str().unwrap()
-                    ));
-                }
+        if let Some(timeout) = self.request_timeout {
+            req = req.timeout(timeout);
+        }
 
-                Ok(data)
+        // Reading the body is part of the request, it can fail or time out too
+        let res = match req.send().await {
+            Ok(o) => {
+                let status = o.status();
+                o.bytes().await.map(|bytes| (status, bytes))
             }
-            Err(e) => Err(e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        if let Some(permit) = permit {
+            permit.record(matches!(&res, Ok((status, _)) if !status.is_server_error()));
+        }
+
+        let (status, bytes) = res.map_err(|e| e.to_string())?;
+        let data: Value = match serde_json::from_slice(&bytes) {
+            Ok(data) => data,
+            Err(_) if !status.is_success() => return Err(format!("HTTP {}", status)),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        if data["errorId"].as_i64().unwrap_or(0) != 0 {
+            return Err(api_error(&data));
         }
+
+        Ok(data)
     }
 }
 
+fn api_error(data: &Value) -> String {
+    format!(
+        "{}: {}",
+        data["errorCode"].as_str().unwrap_or_default(),
+        data["errorDescription"].as_str().unwrap_or_default()
+    )
+}
+
 #[derive(Deserialize)]
 pub struct GetBalance {
     pub balance: f64,
-    pub packages: Vec<String>,
+    #[serde(default)]
+    pub packages: Vec<Package>,
+}
+
+impl GetBalance {
+    /// Packages that have started, have not expired and still have calls left.
+    pub fn active_packages(&self) -> Vec<&Package> {
+        let now = SystemTime::now();
+
+        self.packages
+            .iter()
+            .filter(|p| p.is_active_at(now))
+            .collect()
+    }
+
+    /// Active packages that expire within `within` from now.
+    pub fn expiring_within(&self, within: Duration) -> Vec<&Package> {
+        let now = SystemTime::now();
+        let deadline = now + within;
+
+        self.packages
+            .iter()
+            .filter(|p| p.is_active_at(now))
+            .filter(|p| p.expires_at().is_some_and(|t| t <= deadline))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub package_id: String,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub r#type: String,
+    pub title: Option<String>,
+    /// Remaining quota of the package.
+    pub number_of_calls: Option<i64>,
+    pub status: Option<i64>,
+    /// Unix timestamp at which the package becomes active, see [`Package::starts_at`].
+    pub active_time: Option<u64>,
+    /// Unix timestamp at which the package expires, see [`Package::expires_at`].
+    pub expire_time: Option<u64>,
+}
+
+impl Package {
+    /// `activeTime` as a time. `getBalance` documents it in seconds, unlike the
+    /// milliseconds of token `expireTime`, but millisecond values are accepted too.
+    pub fn starts_at(&self) -> Option<SystemTime> {
+        self.active_time.map(package_time)
+    }
+
+    /// `expireTime` as a time, in seconds or milliseconds like `starts_at`.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expire_time.map(package_time)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(SystemTime::now())
+    }
+
+    fn is_active_at(&self, now: SystemTime) -> bool {
+        let started = self.starts_at().is_none_or(|t| t <= now);
+        let expired = self.expires_at().is_some_and(|t| t <= now);
+        let exhausted = self.number_of_calls.is_some_and(|n| n <= 0);
+
+        started && !expired && !exhausted
+    }
+}
+
+/// Seconds would only reach this far in the year 5138, so larger values are milliseconds.
+fn package_time(t: u64) -> SystemTime {
+    if t >= 100_000_000_000 {
+        UNIX_EPOCH + Duration::from_millis(t)
+    } else {
+        UNIX_EPOCH + Duration::from_secs(t)
+    }
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(s)) => Ok(s),
+        Some(Value::Number(n)) => Ok(n.to_string()),
+        _ => Ok(String::new()),
+    }
 }
 
 pub struct CapSolver {
@@ -108,80 +340,389 @@ impl CapSolver {
         &self.token
     }
 
+    /// State of the circuit breaker, `None` if it isn't enabled.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.config.circuit_breaker.as_ref().map(|b| b.state())
+    }
+
     pub async fn get_balance(&self) -> Result<GetBalance, String> {
         let config = &self.config;
         let body = config.make_body();
 
-        let res = config
-            .client
-            .post(config.api_url.join("getBalance").unwrap())
-            .json(&body)
-            .send()
-            .await;
-
-        match res {
-            Ok(o) => {
-                let data: Value = serde_json::from_str(o.text().await.unwrap().as_str()).unwrap();
-
-                if data["errorId"].as_i64().unwrap() != 0 {
-                    return Err(format!(
-                        "{}: {}",
-                        data["errorCode"].as_str().unwrap(),
-                        data["errorDescription"].as_str().unwrap()
-                    ));
-                }
+        let data = config.post("getBalance", &body).await?;
 
-                Ok(serde_json::from_value::<GetBalance>(data).unwrap())
-            }
-            Err(e) => Err(e.to_string()),
-        }
+        serde_json::from_value::<GetBalance>(data).map_err(|e| e.to_string())
     }
 
     pub async fn create_task(&self, body: &str) -> Result<Value, String> {
         match serde_json::from_str(body) {
             Ok(o) => self.config.create_task(o).await,
-            _ => Err("Inavlid JSON".to_string()),
+            Err(e) => Err(format!("Invalid JSON: {}", e)),
         }
     }
 
-    pub async fn get_task_result<T: DeserializeOwned>(&self, task_id: &str) -> Result<T, String> {
+    /// Creates a task from any serializable `task` object, the client key is
+    /// added to the request for you.
+    pub async fn create_task_raw<T: Serialize>(
+        &self,
+        task: &T,
+    ) -> Result<CreateTaskResponse, String> {
         let config = &self.config;
-        let mut interval = interval(Duration::from_millis(config.interval));
         let mut body = config.make_body();
 
-        body["taskId"] = json!(task_id);
+        body["task"] = serde_json::to_value(task).map_err(|e| e.to_string())?;
 
-        loop {
-            let res = config
-                .client
-                .post(config.api_url.join("getTaskResult").unwrap())
-                .json(&body)
-                .send()
-                .await;
-
-            match res {
-                Ok(o) => {
-                    let data: Value =
-                        serde_json::from_str(o.text().await.unwrap().as_str()).unwrap();
-
-                    if data["errorId"].as_i64().unwrap() != 0 {
-                        return Err(format!(
-                            "{}: {}",
-                            data["errorCode"].as_str().unwrap(),
-                            data["errorDescription"].as_str().unwrap()
-                        ));
-                    }
-
-                    if data["status"].as_str().unwrap() == "ready" {
-                        return Ok(serde_json::from_value::<T>(data["solution"].clone()).unwrap());
-                    }
+        let data = config.create_task(body).await?;
+
+        serde_json::from_value(data).map_err(|e| e.to_string())
+    }
+
+    pub async fn get_task_result<T: DeserializeOwned>(
+        &self,
+        task_id: impl AsRef<str>,
+    ) -> Result<T, String> {
+        self.get_task_result_detailed(task_id)
+            .await
+            .map(|r| r.solution)
+    }
+
+    /// Like `get_task_result`, but keeps the timing and cost metadata of the result.
+    pub async fn get_task_result_detailed<T: DeserializeOwned>(
+        &self,
+        task_id: impl AsRef<str>,
+    ) -> Result<TaskResult<T>, String> {
+        let config = &self.config;
+        let task_id = task_id.as_ref();
+        let mut interval = interval(Duration::from_millis(config.interval));
+
+        #[cfg(feature = "callback")]
+        if let Some((receiver, timeout)) = &config.callback {
+            if let Some(data) = receiver.wait(task_id, *timeout).await {
+                if let Some(result) = task_result::<T>(data)? {
+                    return Ok(result);
                 }
-                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        loop {
+            if let Some(result) = self.poll_task_result::<T>(task_id).await? {
+                return Ok(result);
             }
 
             interval.tick().await;
         }
     }
+
+    async fn poll_task_result<T: DeserializeOwned>(
+        &self,
+        task_id: &str,
+    ) -> Result<Option<TaskResult<T>>, String> {
+        let config = &self.config;
+        let mut body = config.make_body();
+
+        body["taskId"] = json!(task_id);
+
+        task_result::<T>(config.post("getTaskResult", &body).await?)
+    }
+
+    pub async fn get_task_solution<T: DeserializeOwned>(
+        &self,
+        task_id: impl AsRef<str>,
+    ) -> Result<Solution<T>, String> {
+        let task_id = task_id.as_ref();
+
+        Ok(Solution {
+            task_id: task_id.into(),
+            solution: self.get_task_result(task_id).await?,
+        })
+    }
+
+    /// Creates `task` without waiting for it, the returned handle can be waited
+    /// on later, or serialized and resumed by another process.
+    pub async fn submit<T: CapSolverTask>(
+        &self,
+        task: &T,
+    ) -> Result<PendingTask<T::Solution>, String> {
+        self.submit_body(self.config.task_body(task)?).await
+    }
+
+    pub(crate) async fn submit_body<S>(&self, body: Value) -> Result<PendingTask<S>, String> {
+        let config = &self.config;
+        let task_type = body["task"]["type"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let data = config.create_task(body).await?;
+
+        // Recognition tasks are usually solved within `createTask` itself
+        let is_ready = data["status"].as_str() == Some("ready") && !data["solution"].is_null();
+        let task_id: TaskId = match data["taskId"].as_str() {
+            Some(id) => id.into(),
+            None if is_ready => TaskId::default(),
+            None => return Err("Missing taskId".to_string()),
+        };
+        let ready = is_ready.then_some(data);
+
+        Ok(PendingTask::new(
+            task_id,
+            task_type,
+            config.key_fingerprint(),
+            ready,
+        ))
+    }
+
+    /// Creates `task` and waits for its solution, or takes it from the cache
+    /// if one is configured.
+    pub async fn solve<T: CapSolverTask>(&self, task: &T) -> Result<Solution<T::Solution>, String> {
+        let cache = match &self.config.cache {
+            Some(cache) => cache,
+            None => return self.submit(task).await?.wait(self).await,
+        };
+
+        let key = SolutionCache::key(task)?;
+
+        // The cache only saves tasks, failing to read or write it shouldn't fail the solve
+        if let Ok(Some(entry)) = cache.get(&key).await {
+            if let Ok(solution) = serde_json::from_value(entry.solution) {
+                return Ok(Solution {
+                    task_id: entry.task_id,
+                    solution,
+                });
+            }
+        }
+
+        let pending = self.submit(task).await?;
+        let task_id = pending.task_id().clone();
+        let raw = pending.cast::<Value>().wait(self).await?.solution;
+        let solution = serde_json::from_value(raw.clone()).map_err(|e| e.to_string())?;
+
+        if let Some(policy) = task.cache_policy(&solution).filter(|p| p.reusable) {
+            let entry = CacheEntry {
+                task_id: task_id.clone(),
+                solution: raw,
+                expires_at: policy.expires_at,
+                reusable: true,
+            };
+
+            let _ = cache.insert(&key, entry).await;
+        }
+
+        Ok(Solution { task_id, solution })
+    }
+
+    /// Puts a solution that was never used into the cache, so the next `solve` of
+    /// the same task can take it. Returns whether it was stored.
+    pub async fn cache_unused<T>(
+        &self,
+        task: &T,
+        solution: Solution<T::Solution>,
+    ) -> Result<bool, String>
+    where
+        T: CapSolverTask,
+        T::Solution: Serialize,
+    {
+        let (cache, policy) = match (&self.config.cache, task.cache_policy(&solution)) {
+            (Some(cache), Some(policy)) => (cache, policy),
+            _ => return Ok(false),
+        };
+
+        let entry = CacheEntry {
+            task_id: solution.task_id,
+            solution: serde_json::to_value(solution.solution).map_err(|e| e.to_string())?,
+            expires_at: policy.expires_at,
+            reusable: policy.reusable,
+        };
+
+        cache.insert(&SolutionCache::key(task)?, entry).await
+    }
+
+    /// Reports the outcome of a solved task, e.g. a token rejected by the target site.
+    pub async fn feedback(
+        &self,
+        task_id: impl AsRef<str>,
+        result: FeedbackResult,
+    ) -> Result<(), String> {
+        let config = &self.config;
+        let mut body = config.make_body();
+
+        body["taskId"] = json!(task_id.as_ref());
+        body["result"] = json!(result);
+
+        config.post("feedbackTask", &body).await.map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TaskId(String);
+
+impl TaskId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for TaskId {
+    fn from(task_id: String) -> Self {
+        Self(task_id)
+    }
+}
+
+impl From<&str> for TaskId {
+    fn from(task_id: &str) -> Self {
+        Self(task_id.to_string())
+    }
+}
+
+impl AsRef<str> for TaskId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTaskResponse {
+    /// Empty when a task solved right away came back without one.
+    #[serde(default)]
+    pub task_id: TaskId,
+    pub status: Option<String>,
+    /// Set when the task was solved right away, as recognition tasks usually are.
+    pub solution: Option<Value>,
+}
+
+impl CreateTaskResponse {
+    pub fn is_ready(&self) -> bool {
+        self.status.as_deref() == Some("ready") && self.solution.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedbackResult {
+    pub invalid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A task solution that keeps the ID of the task it came from.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Solution<T> {
+    pub task_id: TaskId,
+    pub solution: T,
+}
+
+impl<T> Solution<T> {
+    pub fn into_inner(self) -> T {
+        self.solution
+    }
+
+    pub async fn report(
+        &self,
+        capsolver: &CapSolver,
+        result: FeedbackResult,
+    ) -> Result<(), String> {
+        capsolver.feedback(&self.task_id, result).await
+    }
+
+    pub async fn report_invalid(&self, capsolver: &CapSolver) -> Result<(), String> {
+        let result = FeedbackResult {
+            invalid: true,
+            code: None,
+            message: None,
+        };
+
+        self.report(capsolver, result).await
+    }
+}
+
+impl<T> Deref for Solution<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.solution
+    }
+}
+
+pub(crate) fn task_result<T: DeserializeOwned>(
+    data: Value,
+) -> Result<Option<TaskResult<T>>, String> {
+    if data["errorId"].as_i64().unwrap_or(0) != 0 {
+        return Err(api_error(&data));
+    }
+
+    if data["status"].as_str() != Some("ready") {
+        return Ok(None);
+    }
+
+    let solution =
+        serde_json::from_value::<T>(data["solution"].clone()).map_err(|e| e.to_string())?;
+    // The solution is what was paid for, an odd envelope shouldn't lose it
+    let meta = serde_json::from_value::<TaskMeta>(data).unwrap_or_default();
+
+    Ok(Some(TaskResult { solution, meta }))
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskResult<T> {
+    pub solution: T,
+    pub meta: TaskMeta,
+}
+
+/// The rest of the `getTaskResult` envelope, each field is only set when the API
+/// returns it in a usable shape.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskMeta {
+    #[serde(default, deserialize_with = "lenient")]
+    pub status: Option<String>,
+    /// Unix timestamp (seconds) at which the task was created.
+    #[serde(default, deserialize_with = "lenient")]
+    pub create_time: Option<u64>,
+    /// Unix timestamp (seconds) at which the task was solved.
+    #[serde(default, deserialize_with = "lenient")]
+    pub end_time: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub solve_count: Option<u32>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub cost: Option<f64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub ip: Option<String>,
+}
+
+impl TaskMeta {
+    pub fn duration(&self) -> Option<Duration> {
+        let (start, end) = (self.create_time?, self.end_time?);
+
+        end.checked_sub(start).map(Duration::from_secs)
+    }
+}
+
+/// Takes the value as is or parsed from a string, `None` if it's neither.
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + FromStr,
+{
+    let value = match Option::<Value>::deserialize(deserializer)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    if let Value::String(s) = &value {
+        if let Ok(parsed) = s.parse() {
+            return Ok(Some(parsed));
+        }
+    }
+
+    Ok(serde_json::from_value(value).ok())
 }
 
 pub struct Recognition {
@@ -189,98 +730,212 @@ pub struct Recognition {
 }
 
 impl Recognition {
+    async fn create_task<T: DeserializeOwned>(
+        &self,
+        body: Value,
+    ) -> Result<RecognitionResult<T>, String> {
+        // Tasks that aren't solved inline are polled like any other
+        let capsolver = CapSolver::new(self.config.clone());
+        let pending = capsolver.submit_body::<T>(body).await?;
+        let result = pending.wait_detailed(&capsolver).await?;
+
+        Ok(RecognitionResult {
+            task_id: pending.into_id(),
+            status: result.meta.status,
+            solution: result.solution,
+        })
+    }
+
     pub async fn image_to_text(
         &self,
-        img: String,
+        img: impl Into<ImageInput>,
         module: Option<&str>,
         score: Option<f64>,
         case_sensitive: Option<bool>,
-    ) -> Result<Value, String> {
+    ) -> Result<RecognitionResult<ImageToTextSolution>, String> {
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
         task["type"] = json!("ImageToTextTask");
-        task["body"] = json!(img);
-
-        match module {
-            Some(m) => {
-                if !SUPPORTED_MODULES.contains(&m) {
-                    return Err("Unsupported module".to_string());
-                }
+        task["body"] = json!(img.into().to_base64().await?);
 
-                task["module"] = json!(module);
+        if let Some(m) = module {
+            if !SUPPORTED_MODULES.contains(&m) {
+                return Err("Unsupported module".to_string());
             }
-            _ => {}
-        }
 
-        match score {
-            Some(s) => {
-                if s > 1.0 || s < 0.8 {
-                    return Err("Score must be within 0.8 ~ 1".to_string());
-                }
+            task["module"] = json!(m);
+        }
 
-                task["score"] = json!(s);
+        if let Some(s) = score {
+            if !(0.8..=1.0).contains(&s) {
+                return Err("Score must be within 0.8 ~ 1".to_string());
             }
-            _ => {}
-        };
+
+            task["score"] = json!(s);
+        }
 
         task["case"] = json!(case_sensitive.unwrap_or(false));
 
-        config.create_task(body).await
+        self.create_task(body).await
     }
 
-    pub async fn h_captcha(&self, queries: Vec<String>, question: &str) -> Result<Value, String> {
+    pub async fn h_captcha<I>(
+        &self,
+        queries: I,
+        question: &str,
+    ) -> Result<RecognitionResult<HCaptchaClassificationSolution>, String>
+    where
+        I: IntoIterator,
+        I::Item: Into<ImageInput>,
+    {
         let config = &self.config;
         let mut body = config.make_body();
 
         body["task"] = json!({
             "type": "HCaptchaClassification",
-            "queries": queries,
+            "queries": to_base64_all(queries).await?,
             "question": question
         });
 
-        config.create_task(body).await
+        self.create_task(body).await
     }
 
-    pub async fn fun_captcha(&self, imgs: Vec<String>, question: &str) -> Result<Value, String> {
+    pub async fn fun_captcha<I>(
+        &self,
+        imgs: I,
+        question: &str,
+    ) -> Result<RecognitionResult<FunCaptchaClassificationSolution>, String>
+    where
+        I: IntoIterator,
+        I::Item: Into<ImageInput>,
+    {
         let config = &self.config;
         let mut body = config.make_body();
 
         body["task"] = json!({
             "type": "FunCaptchaClassification",
-            "images": imgs,
+            "images": to_base64_all(imgs).await?,
             "question": question
         });
 
-        config.create_task(body).await
+        self.create_task(body).await
     }
 
-    pub async fn re_captcha(&self, img: String, question: &str) -> Result<Value, String> {
+    pub async fn re_captcha(
+        &self,
+        img: impl Into<ImageInput>,
+        question: &str,
+    ) -> Result<RecognitionResult<ReCaptchaClassificationSolution>, String> {
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
         task["type"] = json!("ReCaptchaV2Classification");
-        task["image"] = json!(img);
+        task["image"] = json!(img.into().to_base64().await?);
         task["question"] = json!(question);
 
-        config.create_task(body).await
+        self.create_task(body).await
     }
 
-    pub async fn aws_waf(&self, imgs: Vec<String>, question: &str) -> Result<Value, String> {
+    pub async fn aws_waf<I>(
+        &self,
+        imgs: I,
+        question: &str,
+    ) -> Result<RecognitionResult<AwsWafClassificationSolution>, String>
+    where
+        I: IntoIterator,
+        I::Item: Into<ImageInput>,
+    {
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
         task["type"] = json!("AwsWafClassification");
-        task["images"] = json!(imgs);
+        task["images"] = json!(to_base64_all(imgs).await?);
         task["question"] = json!(question);
 
-        config.create_task(body).await
+        self.create_task(body).await
     }
 }
 
+async fn to_base64_all<I>(imgs: I) -> Result<Vec<String>, String>
+where
+    I: IntoIterator,
+    I::Item: Into<ImageInput>,
+{
+    let mut encoded = Vec::new();
+
+    for img in imgs {
+        encoded.push(img.into().to_base64().await?);
+    }
+
+    Ok(encoded)
+}
+
+/// Result of a recognition task, usually solved inline by `createTask`.
+///
+/// API errors are returned as `Err`, so only solved tasks end up here.
+#[derive(Debug, Clone)]
+pub struct RecognitionResult<T> {
+    pub task_id: TaskId,
+    pub status: Option<String>,
+    pub solution: T,
+}
+
+impl<T> RecognitionResult<T> {
+    pub fn into_solution(self) -> Solution<T> {
+        Solution {
+            task_id: self.task_id,
+            solution: self.solution,
+        }
+    }
+}
+
+impl<T> Deref for RecognitionResult<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.solution
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImageToTextSolution {
+    pub text: String,
+    pub confidence: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HCaptchaClassificationSolution {
+    #[serde(default)]
+    pub objects: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReCaptchaClassificationSolution {
+    #[serde(default)]
+    pub objects: Vec<usize>,
+    pub size: Option<usize>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FunCaptchaClassificationSolution {
+    #[serde(default)]
+    pub objects: Vec<usize>,
+}
+
+/// Depending on the question, AWS WAF returns either the matching `objects`
+/// or a `box`/`distance` to drag to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AwsWafClassificationSolution {
+    pub objects: Option<Vec<usize>>,
+    pub r#box: Option<Vec<f64>>,
+    pub distance: Option<f64>,
+}
+
 pub struct Token {
     config: Config,
 }
@@ -295,6 +950,20 @@ pub struct HCaptchaToken {
     pub g_recaptcha_response: String,
 }
 
+impl HCaptchaToken {
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        expire_time_ms(self.expire_time)
+    }
+}
+
+/// `expireTime` as returned with tokens, a Unix timestamp in milliseconds.
+fn expire_time_ms(expire_time: isize) -> Option<SystemTime> {
+    u64::try_from(expire_time)
+        .ok()
+        .filter(|t| *t > 0)
+        .map(|t| UNIX_EPOCH + Duration::from_millis(t))
+}
+
 #[derive(Deserialize)]
 pub struct OnlyToken {
     pub token: String,
@@ -325,11 +994,31 @@ pub struct ReCaptchaToken {
     pub g_recaptcha_response: String,
 }
 
+impl ReCaptchaToken {
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        expire_time_ms(self.expire_time)
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DataDomeToken {
+    #[serde(default)]
     pub user_agent: String,
-    pub cookie: isize,
+    /// `Set-Cookie` style string, e.g. `datadome=...; Max-Age=31536000; Domain=.example.com; Path=/`.
+    pub cookie: String,
+}
+
+#[cfg(feature = "cookies")]
+impl DataDomeToken {
+    pub fn parse_cookie(&self) -> Result<cookie::Cookie<'static>, String> {
+        cookie::Cookie::parse(self.cookie.clone()).map_err(|e| e.to_string())
+    }
+
+    /// Adds the cookie to `jar` as if it was set by a response from `url`.
+    pub fn add_to_jar(&self, jar: &reqwest::cookie::Jar, url: &Url) {
+        jar.add_cookie_str(&self.cookie, url);
+    }
 }
 
 #[derive(Deserialize)]
@@ -337,51 +1026,67 @@ pub struct AwsWafToken {
     pub cookie: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct CyberSiAraToken {
+    pub token: String,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CloudfareToken {
+pub struct CloudflareTurnstileToken {
     pub token: String,
-    pub r#type: String,
-    pub user_agent: String,
+    pub r#type: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[deprecated(note = "use `CloudflareTurnstileToken`")]
+pub type CloudfareToken = CloudflareTurnstileToken;
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudflareChallengeToken {
+    /// Cookies to send along with the solved request, e.g. `cf_clearance`.
+    #[serde(default)]
+    pub cookies: HashMap<String, String>,
+    pub token: Option<String>,
+    pub r#type: Option<String>,
+    pub user_agent: Option<String>,
 }
 
 impl Token {
+    #[allow(clippy::too_many_arguments)]
     pub async fn h_captcha(
         &self,
-        r#type: &str,
+        r#type: Option<HCaptchaType>,
         website_url: &str,
         website_key: &str,
         is_invisible: Option<bool>,
-        proxy: Option<String>,
-        enterprise_payload: Option<HashMap<&str, String>>,
+        proxy: Option<Proxy>,
+        enterprise_payload: Option<HCaptchaEnterprisePayload>,
         user_agent: Option<&str>,
     ) -> Result<Value, String> {
+        let r#type = HCaptchaType::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
-        task["type"] = json!("ImageToTextTask");
         task["websiteURL"] = json!(website_url);
         task["websiteKey"] = json!(website_key);
 
-        if is_invisible.is_some() {
-            task["isInvisible"] = json!(is_invisible.unwrap());
-        }
-
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
+        if let Some(is_invisible) = is_invisible {
+            task["isInvisible"] = json!(is_invisible);
         }
 
-        if enterprise_payload.is_some() {
-            task["enterprisePayload"] = json!(enterprise_payload.unwrap());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
-        if user_agent.is_some() {
-            task["userAgent"] = json!(user_agent.unwrap());
+        if let Some(enterprise_payload) = enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
         }
 
-        if !H_CAPTCHA_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(user_agent) = user_agent {
+            task["userAgent"] = json!(user_agent);
         }
 
         task["type"] = json!(r#type);
@@ -395,69 +1100,67 @@ impl Token {
         website_public_key: &str,
         fun_captcha_api_js_subdomain: Option<String>,
         data: Option<String>,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
     ) -> Result<Value, String> {
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
-        task["type"] = json!("FunCaptchaTaskProxyLess");
+        task["type"] = json!(FunCaptchaType::resolve(None, proxy.is_some())?);
         task["websiteURL"] = json!(website_url);
         task["websitePublicKey"] = json!(website_public_key);
 
-        if fun_captcha_api_js_subdomain.is_some() {
-            task["funcaptchaApiJSSubdomain"] = json!(fun_captcha_api_js_subdomain.unwrap());
+        if let Some(fun_captcha_api_js_subdomain) = fun_captcha_api_js_subdomain {
+            task["funcaptchaApiJSSubdomain"] = json!(fun_captcha_api_js_subdomain);
         }
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
-        if data.is_some() {
-            task["data"] = json!(data.unwrap());
+        if let Some(data) = data {
+            task["data"] = json!(data);
         }
 
         config.create_task(body).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn gee_test(
         &self,
-        r#type: &str,
+        r#type: Option<GeeTestType>,
         website_url: &str,
         gt: Option<String>,
         challenge: Option<String>,
         captcha_id: Option<String>,
         gee_test_api_server_subdomain: Option<String>,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
     ) -> Result<Value, String> {
+        let r#type = GeeTestType::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
         task["websiteURL"] = json!(website_url);
 
-        if gt.is_some() {
-            task["gt"] = json!(gt.unwrap());
-        }
-
-        if challenge.is_some() {
-            task["challenge"] = json!(challenge.unwrap());
+        if let Some(gt) = gt {
+            task["gt"] = json!(gt);
         }
 
-        if captcha_id.is_some() {
-            task["captchaId"] = json!(captcha_id.unwrap());
+        if let Some(challenge) = challenge {
+            task["challenge"] = json!(challenge);
         }
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
+        if let Some(captcha_id) = captcha_id {
+            task["captchaId"] = json!(captcha_id);
         }
 
-        if gee_test_api_server_subdomain.is_some() {
-            task["geetestApiServerSubdomain"] = json!(gee_test_api_server_subdomain.unwrap());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
-        if !GEE_TEST_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(gee_test_api_server_subdomain) = gee_test_api_server_subdomain {
+            task["geetestApiServerSubdomain"] = json!(gee_test_api_server_subdomain);
         }
 
         task["type"] = json!(r#type);
@@ -465,21 +1168,23 @@ impl Token {
         config.create_task(body).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn re_captcha_v2(
         &self,
-        r#type: &str,
+        r#type: Option<ReCaptchaV2Type>,
         website_url: &str,
         website_key: &str,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
         page_action: Option<String>,
-        enterprise_payload: Option<HashMap<&str, String>>,
+        enterprise_payload: Option<ReCaptchaEnterprisePayload>,
         is_invisible: Option<bool>,
         api_domain: Option<String>,
         user_agent: Option<&str>,
-        cookies: Option<Vec<HashMap<String, String>>>,
+        cookies: Option<Vec<TaskCookie>>,
         anchor: Option<String>,
         reload: Option<String>,
     ) -> Result<Value, String> {
+        let r#type = ReCaptchaV2Type::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
@@ -487,44 +1192,40 @@ impl Token {
         task["websiteURL"] = json!(website_url);
         task["websiteKey"] = json!(website_key);
 
-        if page_action.is_some() {
-            task["pageAction"] = json!(page_action.unwrap());
+        if let Some(page_action) = page_action {
+            task["pageAction"] = json!(page_action);
         }
 
-        if api_domain.is_some() {
-            task["apiDomain"] = json!(api_domain.unwrap());
+        if let Some(api_domain) = api_domain {
+            task["apiDomain"] = json!(api_domain);
         }
 
-        if cookies.is_some() {
-            task["cookies"] = json!(cookies.unwrap());
+        if let Some(cookies) = cookies {
+            task["cookies"] = json!(cookies);
         }
 
-        if anchor.is_some() {
-            task["anchor"] = json!(anchor.unwrap());
+        if let Some(anchor) = anchor {
+            task["anchor"] = json!(anchor);
         }
 
-        if reload.is_some() {
-            task["reload"] = json!(reload.unwrap());
+        if let Some(reload) = reload {
+            task["reload"] = json!(reload);
         }
 
-        if is_invisible.is_some() {
-            task["isInvisible"] = json!(is_invisible.unwrap());
+        if let Some(is_invisible) = is_invisible {
+            task["isInvisible"] = json!(is_invisible);
         }
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
-        if enterprise_payload.is_some() {
-            task["enterprisePayload"] = json!(enterprise_payload.unwrap());
+        if let Some(enterprise_payload) = enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
         }
 
-        if user_agent.is_some() {
-            task["userAgent"] = json!(user_agent.unwrap());
-        }
-
-        if !RE_CAPTCHA_V2_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(user_agent) = user_agent {
+            task["userAgent"] = json!(user_agent);
         }
 
         task["type"] = json!(r#type);
@@ -532,21 +1233,23 @@ impl Token {
         config.create_task(body).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn re_captcha_v3(
         &self,
-        r#type: &str,
+        r#type: Option<ReCaptchaV3Type>,
         website_url: &str,
         website_key: &str,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
         min_score: Option<f64>,
         page_action: String,
-        enterprise_payload: Option<HashMap<&str, String>>,
+        enterprise_payload: Option<ReCaptchaEnterprisePayload>,
         api_domain: Option<String>,
         user_agent: Option<&str>,
-        cookies: Option<Vec<HashMap<String, String>>>,
+        cookies: Option<Vec<TaskCookie>>,
         anchor: Option<String>,
         reload: Option<String>,
     ) -> Result<Value, String> {
+        let r#type = ReCaptchaV3Type::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
@@ -555,40 +1258,36 @@ impl Token {
         task["websiteKey"] = json!(website_key);
         task["pageAction"] = json!(page_action);
 
-        if min_score.is_some() {
-            task["minScore"] = json!(min_score.unwrap())
+        if let Some(min_score) = min_score {
+            task["minScore"] = json!(min_score)
         }
 
-        if api_domain.is_some() {
-            task["apiDomain"] = json!(api_domain.unwrap());
+        if let Some(api_domain) = api_domain {
+            task["apiDomain"] = json!(api_domain);
         }
 
-        if cookies.is_some() {
-            task["cookies"] = json!(cookies.unwrap());
+        if let Some(cookies) = cookies {
+            task["cookies"] = json!(cookies);
         }
 
-        if anchor.is_some() {
-            task["anchor"] = json!(anchor.unwrap());
+        if let Some(anchor) = anchor {
+            task["anchor"] = json!(anchor);
         }
 
-        if reload.is_some() {
-            task["reload"] = json!(reload.unwrap());
+        if let Some(reload) = reload {
+            task["reload"] = json!(reload);
         }
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
-        if enterprise_payload.is_some() {
-            task["enterprisePayload"] = json!(enterprise_payload.unwrap());
+        if let Some(enterprise_payload) = enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
         }
 
-        if user_agent.is_some() {
-            task["userAgent"] = json!(user_agent.unwrap());
-        }
-
-        if !RE_CAPTCHA_V3_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(user_agent) = user_agent {
+            task["userAgent"] = json!(user_agent);
         }
 
         task["type"] = json!(r#type);
@@ -598,11 +1297,12 @@ impl Token {
 
     pub async fn mt_captcha(
         &self,
-        r#type: &str,
+        r#type: Option<MtCaptchaType>,
         website_url: &str,
         website_key: &str,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
     ) -> Result<Value, String> {
+        let r#type = MtCaptchaType::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
@@ -610,12 +1310,8 @@ impl Token {
         task["websiteURL"] = json!(website_url);
         task["websiteKey"] = json!(website_key);
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
-        }
-
-        if !MT_CAPTCHA_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
         task["type"] = json!(r#type);
@@ -627,7 +1323,7 @@ impl Token {
         &self,
         website_url: &str,
         captcha_url: &str,
-        proxy: String,
+        proxy: Proxy,
         user_agent: &str,
     ) -> Result<Value, String> {
         let config = &self.config;
@@ -646,22 +1342,19 @@ impl Token {
 
     pub async fn aws_waf(
         &self,
-        r#type: &str,
+        r#type: Option<AwsWafType>,
         website_url: &str,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
     ) -> Result<Value, String> {
+        let r#type = AwsWafType::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
         let task = &mut body["task"];
 
         task["websiteURL"] = json!(website_url);
 
-        if proxy.is_some() {
-            task["proxy"] = json!(proxy.unwrap());
-        }
-
-        if !AWS_WAF_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
+        if let Some(proxy) = proxy {
+            task["proxy"] = json!(proxy);
         }
 
         task["type"] = json!(r#type);
@@ -671,72 +1364,107 @@ impl Token {
 
     pub async fn cyber_si_ara(
         &self,
-        r#type: &str,
+        r#type: Option<CyberSiAraType>,
         slide_master_url_id: &str,
         website_url: &str,
         user_agent: &str,
-        proxy: Option<String>,
+        proxy: Option<Proxy>,
     ) -> Result<Value, String> {
+        let r#type = CyberSiAraType::resolve(r#type, proxy.is_some())?;
         let config = &self.config;
         let mut body = config.make_body();
 
-        if !CYBER_SI_ARA_TYPES.contains(&r#type) {
-            return Err("Unsupported type".to_string());
-        }
-
         body["task"] = json!({
             "type": r#type,
-            "SlideMasterURLId": slide_master_url_id,
+            "SlideMasterUrlId": slide_master_url_id,
             "websiteURL": website_url,
-            "userAgent": user_agent
+            "UserAgent": user_agent
         });
 
-        if proxy.is_some() {
-            body["task"]["proxy"] = json!(proxy.unwrap());
+        if let Some(proxy) = proxy {
+            body["task"]["proxy"] = json!(proxy);
         }
 
         config.create_task(body).await
     }
 
+    pub async fn cloudflare_turnstile(
+        &self,
+        website_url: &str,
+        website_key: &str,
+        metadata: Option<TurnstileMetadata>,
+        proxy: Option<Proxy>,
+    ) -> Result<Value, String> {
+        let task = TurnstileTask {
+            r#type: None,
+            website_url: website_url.to_string(),
+            website_key: website_key.to_string(),
+            metadata,
+            proxy,
+        };
+
+        self.create_task(&task).await
+    }
+
+    pub async fn cloudflare_challenge(
+        &self,
+        website_url: &str,
+        html: Option<&str>,
+        user_agent: Option<&str>,
+        proxy: Proxy,
+    ) -> Result<Value, String> {
+        let task = CloudflareChallengeTask {
+            website_url: website_url.to_string(),
+            html: html.map(str::to_string),
+            user_agent: user_agent.map(str::to_string),
+            proxy,
+            reuse_for: None,
+        };
+
+        self.create_task(&task).await
+    }
+
+    #[deprecated(note = "use `cloudflare_turnstile`")]
     pub async fn cloudfare_turnstile(
         &self,
         website_url: &str,
         website_key: &str,
         metadata: HashMap<&str, &str>,
-        proxy: &str,
+        proxy: Proxy,
     ) -> Result<Value, String> {
-        let config = &self.config;
-        let mut body = config.make_body();
-
-        body["task"] = json!({
-            "type": "AntiCloudflareTask",
-            "websiteURL": website_url,
-            "websiteKey": website_key,
-            "metadata": metadata,
-            "proxy": proxy
-        });
+        let metadata = TurnstileMetadata {
+            action: metadata.get("action").map(|s| s.to_string()),
+            cdata: metadata.get("cdata").map(|s| s.to_string()),
+        };
 
-        config.create_task(body).await
+        self.cloudflare_turnstile(website_url, website_key, Some(metadata), Some(proxy))
+            .await
     }
 
+    #[deprecated(note = "use `cloudflare_challenge`")]
     pub async fn cloudfare_challange(
         &self,
         website_url: &str,
         metadata: HashMap<&str, &str>,
         html: &str,
-        proxy: &str,
+        proxy: Proxy,
     ) -> Result<Value, String> {
         let config = &self.config;
-        let mut body = config.make_body();
+        let mut task = CloudflareChallengeTask::new(website_url, proxy);
+        task.html = Some(html.to_string());
 
-        body["task"] = json!({
-            "type": "AntiCloudflareTask",
-            "websiteURL": website_url,
-            "html": html,
-            "metadata": metadata,
-            "proxy": proxy
-        });
+        let mut body = config.task_body(&task)?;
+
+        if !metadata.is_empty() {
+            body["task"]["metadata"] = json!(metadata);
+        }
 
         config.create_task(body).await
     }
+
+    async fn create_task<T: CapSolverTask>(&self, task: &T) -> Result<Value, String> {
+        let config = &self.config;
+
+        config.create_task(config.task_body(task)?).await
+    }
 }
@@ -0,0 +1,130 @@
+use crate::{task_result, CapSolver, Solution, TaskId, TaskResult};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{marker::PhantomData, time::SystemTime};
+
+/// A created task whose solution hasn't been fetched yet.
+///
+/// It can be serialized and handed to another process, which resumes waiting
+/// with its own [`CapSolver`] as long as it uses the same client key.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTask<S> {
+    task_id: TaskId,
+    task_type: String,
+    created_at: SystemTime,
+    key_fingerprint: String,
+    /// The `createTask` response, when it already carried the solution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ready: Option<Value>,
+    #[serde(skip)]
+    solution: PhantomData<fn() -> S>,
+}
+
+impl<S> PendingTask<S> {
+    pub(crate) fn new(
+        task_id: TaskId,
+        task_type: String,
+        key_fingerprint: String,
+        ready: Option<Value>,
+    ) -> Self {
+        Self {
+            task_id,
+            task_type,
+            created_at: SystemTime::now(),
+            key_fingerprint,
+            ready,
+            solution: PhantomData,
+        }
+    }
+
+    pub fn task_id(&self) -> &TaskId {
+        &self.task_id
+    }
+
+    pub fn task_type(&self) -> &str {
+        &self.task_type
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    /// Whether the solution came back with `createTask`, so waiting won't poll.
+    pub fn is_ready(&self) -> bool {
+        self.ready.is_some()
+    }
+
+    pub fn into_id(self) -> TaskId {
+        self.task_id
+    }
+
+    /// The same task, deserializing its solution as `U`.
+    pub(crate) fn cast<U>(self) -> PendingTask<U> {
+        PendingTask {
+            task_id: self.task_id,
+            task_type: self.task_type,
+            created_at: self.created_at,
+            key_fingerprint: self.key_fingerprint,
+            ready: self.ready,
+            solution: PhantomData,
+        }
+    }
+
+    fn check_key(&self, capsolver: &CapSolver) -> Result<(), String> {
+        if capsolver.config.key_fingerprint() != self.key_fingerprint {
+            return Err(format!(
+                "Task {} was created with a different client key",
+                self.task_id
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: DeserializeOwned> PendingTask<S> {
+    /// Waits until the task is solved.
+    pub async fn wait(&self, capsolver: &CapSolver) -> Result<Solution<S>, String> {
+        let result = self.wait_detailed(capsolver).await?;
+
+        Ok(self.solution(result))
+    }
+
+    /// Like `wait`, but keeps the timing and cost metadata of the result.
+    pub async fn wait_detailed(&self, capsolver: &CapSolver) -> Result<TaskResult<S>, String> {
+        self.check_key(capsolver)?;
+
+        if let Some(result) = self.ready_result()? {
+            return Ok(result);
+        }
+
+        capsolver.get_task_result_detailed(&self.task_id).await
+    }
+
+    /// Checks the task once, returning `None` if it isn't solved yet.
+    pub async fn poll_once(&self, capsolver: &CapSolver) -> Result<Option<Solution<S>>, String> {
+        self.check_key(capsolver)?;
+
+        let result = match self.ready_result()? {
+            Some(result) => Some(result),
+            None => capsolver.poll_task_result(self.task_id.as_str()).await?,
+        };
+
+        Ok(result.map(|r| self.solution(r)))
+    }
+
+    fn ready_result(&self) -> Result<Option<TaskResult<S>>, String> {
+        match &self.ready {
+            Some(data) => task_result(data.clone()),
+            None => Ok(None),
+        }
+    }
+
+    fn solution(&self, result: TaskResult<S>) -> Solution<S> {
+        Solution {
+            task_id: self.task_id.clone(),
+            solution: result.solution,
+        }
+    }
+}
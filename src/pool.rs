@@ -0,0 +1,280 @@
+use crate::{ApiErrorCode, CapSolver, CapSolverTask, ErrorCategory, Solution};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::Notify,
+    task::{JoinHandle, JoinSet},
+    time::{sleep, sleep_until, timeout, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Tokens kept ready, counting the ones being solved.
+    pub target: usize,
+    /// Tokens are discarded this long before they expire.
+    pub margin: Duration,
+    /// Lifetime of tokens whose task doesn't tell when they expire, see
+    /// [`CapSolverTask::cache_policy`].
+    pub ttl: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            target: 2,
+            margin: Duration::from_secs(10),
+            ttl: Duration::from_secs(120),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PoolStats {
+    pub solved: u64,
+    pub taken: u64,
+    /// Tokens that came too close to expiring before anyone took them.
+    pub wasted: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+impl PoolStats {
+    /// Share of solved tokens that were wasted, lower the target if it's high.
+    pub fn waste_rate(&self) -> f64 {
+        match self.solved {
+            0 => 0.0,
+            n => self.wasted as f64 / n as f64,
+        }
+    }
+}
+
+struct Shared<S> {
+    tokens: Mutex<Vec<(Solution<S>, SystemTime)>>,
+    stats: Mutex<PoolStats>,
+    margin: Duration,
+    /// Set once refilling stopped on an error that retrying won't fix.
+    fatal: Mutex<Option<String>>,
+    /// Wakes `take` when a token is added or refilling stops.
+    added: Notify,
+    /// Wakes the refill worker when a token is taken.
+    taken: Notify,
+}
+
+impl<S> Shared<S> {
+    /// Drops tokens within the margin of their expiry, returning how many are left.
+    fn purge(&self) -> usize {
+        let deadline = SystemTime::now() + self.margin;
+        let mut tokens = self.tokens.lock().unwrap();
+        let before = tokens.len();
+
+        tokens.retain(|(_, expires_at)| *expires_at > deadline);
+        self.stats.lock().unwrap().wasted += (before - tokens.len()) as u64;
+
+        tokens.len()
+    }
+
+    /// Takes the fresh token closest to expiring.
+    fn pop(&self) -> Option<Solution<S>> {
+        self.purge();
+
+        let mut tokens = self.tokens.lock().unwrap();
+        let (i, _) = tokens
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, expires_at))| *expires_at)?;
+        let (solution, _) = tokens.remove(i);
+
+        self.stats.lock().unwrap().taken += 1;
+        self.taken.notify_one();
+
+        Some(solution)
+    }
+
+    /// When the next token has to be discarded.
+    fn next_discard(&self) -> Option<Instant> {
+        let now = SystemTime::now();
+        let earliest = self.tokens.lock().unwrap().iter().map(|(_, e)| *e).min()?;
+        let left = earliest
+            .duration_since(now + self.margin)
+            .unwrap_or_default();
+
+        Some(Instant::now() + left)
+    }
+}
+
+/// Keeps `target` solutions of a task ready, refilling in the background.
+///
+/// Dropping the pool stops refilling it, and so does an error every further
+/// task would fail with.
+pub struct TokenPool<T: CapSolverTask> {
+    shared: Arc<Shared<T::Solution>>,
+    worker: JoinHandle<()>,
+}
+
+impl<T> TokenPool<T>
+where
+    T: CapSolverTask + Send + Sync + 'static,
+    T::Solution: Send + 'static,
+{
+    /// Starts filling the pool, must be called within a tokio runtime.
+    pub fn new(capsolver: &CapSolver, task: T, options: PoolOptions) -> Self {
+        let shared = Arc::new(Shared {
+            tokens: Mutex::new(Vec::new()),
+            stats: Mutex::new(PoolStats::default()),
+            margin: options.margin,
+            fatal: Mutex::new(None),
+            added: Notify::new(),
+            taken: Notify::new(),
+        });
+
+        let capsolver = Arc::new(CapSolver::new(capsolver.config.clone()));
+        let worker = tokio::spawn(refill(capsolver, Arc::new(task), shared.clone(), options));
+
+        Self { shared, worker }
+    }
+
+    /// Waits for a fresh token.
+    ///
+    /// Keeps waiting while refilling fails with errors that may go away, see
+    /// [`PoolStats::last_error`]. Fails once the pool is empty and refilling
+    /// stopped on an auth, billing, input or proxy error.
+    pub async fn take(&self) -> Result<Solution<T::Solution>, String> {
+        loop {
+            let added = self.shared.added.notified();
+            tokio::pin!(added);
+            added.as_mut().enable();
+
+            if let Some(solution) = self.shared.pop() {
+                return Ok(solution);
+            }
+
+            if let Some(e) = &*self.shared.fatal.lock().unwrap() {
+                return Err(e.clone());
+            }
+
+            added.await;
+        }
+    }
+
+    /// Like `take`, but gives up after `duration`.
+    pub async fn take_timeout(&self, duration: Duration) -> Result<Solution<T::Solution>, String> {
+        timeout(duration, self.take())
+            .await
+            .map_err(|_| "Timed out waiting for a token".to_string())?
+    }
+
+    /// Takes a fresh token if one is ready.
+    pub fn try_take(&self) -> Option<Solution<T::Solution>> {
+        self.shared.pop()
+    }
+
+    /// Fresh tokens ready to be taken.
+    pub fn ready(&self) -> usize {
+        self.shared.purge()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.shared.stats.lock().unwrap().clone()
+    }
+}
+
+impl<T: CapSolverTask> Drop for TokenPool<T> {
+    fn drop(&mut self) {
+        self.worker.abort();
+    }
+}
+
+async fn refill<T>(
+    capsolver: Arc<CapSolver>,
+    task: Arc<T>,
+    shared: Arc<Shared<T::Solution>>,
+    options: PoolOptions,
+) where
+    T: CapSolverTask + Send + Sync + 'static,
+    T::Solution: Send + 'static,
+{
+    let backoff = Duration::from_millis(capsolver.config.interval);
+    let mut solving = JoinSet::new();
+
+    if let Err(e) = task.validate() {
+        shared.stats.lock().unwrap().last_error = Some(e.clone());
+        return stop(&shared, e);
+    }
+
+    loop {
+        while shared.purge() + solving.len() < options.target {
+            let capsolver = capsolver.clone();
+            let task = task.clone();
+
+            solving.spawn(async move {
+                let res = match capsolver.submit(&*task).await {
+                    Ok(pending) => pending.wait(&capsolver).await,
+                    Err(e) => Err(e),
+                };
+
+                // Keep the slot taken for a while, so failures don't turn into a busy loop
+                if matches!(&res, Err(e) if !is_fatal(e)) {
+                    sleep(backoff).await;
+                }
+
+                res.map(|solution| {
+                    let expires_at = task
+                        .cache_policy(&solution)
+                        .map(|p| p.expires_at)
+                        .unwrap_or_else(|| SystemTime::now() + options.ttl);
+
+                    (solution, expires_at)
+                })
+            });
+        }
+
+        let next_discard = shared.next_discard();
+
+        tokio::select! {
+            Some(res) = solving.join_next() => {
+                let error = match res {
+                    Ok(Ok(token)) => {
+                        shared.tokens.lock().unwrap().push(token);
+                        shared.stats.lock().unwrap().solved += 1;
+                        shared.added.notify_waiters();
+                        continue;
+                    }
+                    Ok(Err(e)) => e,
+                    Err(e) => e.to_string(),
+                };
+
+                {
+                    let mut stats = shared.stats.lock().unwrap();
+                    stats.failed += 1;
+                    stats.last_error = Some(error.clone());
+                }
+
+                if is_fatal(&error) {
+                    return stop(&shared, error);
+                }
+            }
+            _ = shared.taken.notified() => {}
+            _ = sleep_until(next_discard.unwrap_or_else(Instant::now)), if next_discard.is_some() => {}
+        }
+    }
+}
+
+/// Errors every further task would fail with too.
+fn is_fatal(error: &str) -> bool {
+    ApiErrorCode::from_error(error).is_some_and(|c| {
+        matches!(
+            c.category(),
+            ErrorCategory::Auth
+                | ErrorCategory::Billing
+                | ErrorCategory::Input
+                | ErrorCategory::Proxy
+        )
+    })
+}
+
+fn stop<S>(shared: &Shared<S>, error: String) {
+    *shared.fatal.lock().unwrap() = Some(error);
+    shared.added.notify_waiters();
+}
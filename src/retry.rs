@@ -0,0 +1,191 @@
+use crate::{
+    ApiErrorCode, CapSolver, CapSolverTask, ErrorCategory, FeedbackResult, Solution, TaskId,
+};
+use std::{
+    future::{ready, Future, Ready},
+    time::Duration,
+};
+use tokio::time::sleep;
+
+pub struct RetryOptions<V> {
+    /// Tasks created at most, including the first one.
+    pub max_attempts: u32,
+    /// No new task is created once the cost so far, plus the average cost of an
+    /// attempt, would exceed this.
+    pub max_total_cost: Option<f64>,
+    /// Checks a solution against the target site, rejected solutions are
+    /// reported as invalid and a new task is created. See [`accept_all`].
+    pub verifier: V,
+}
+
+/// A verifier that accepts every solution.
+pub fn accept_all<S>(_: &S) -> Ready<bool> {
+    ready(true)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttemptOutcome {
+    Solved,
+    /// The verifier rejected the solution.
+    Rejected,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    /// `None` if the task couldn't be created.
+    pub task_id: Option<TaskId>,
+    pub cost: Option<f64>,
+    pub outcome: AttemptOutcome,
+}
+
+#[derive(Debug)]
+pub struct RetryOutcome<S> {
+    pub result: Result<Solution<S>, String>,
+    pub attempts: Vec<RetryAttempt>,
+}
+
+impl<S> RetryOutcome<S> {
+    pub fn total_cost(&self) -> f64 {
+        self.attempts.iter().filter_map(|a| a.cost).sum()
+    }
+
+    pub fn into_result(self) -> Result<Solution<S>, String> {
+        self.result
+    }
+}
+
+impl CapSolver {
+    /// Solves `task`, creating it again when it fails to solve or the verifier
+    /// rejects the solution, until `options` runs out of attempts or budget.
+    ///
+    /// After a transient error, e.g. a rate limit, it waits the config's polling
+    /// interval before trying again.
+    pub async fn solve_with_retry<T, V, F>(
+        &self,
+        task: &T,
+        options: RetryOptions<V>,
+    ) -> RetryOutcome<T::Solution>
+    where
+        T: CapSolverTask,
+        V: Fn(&T::Solution) -> F,
+        F: Future<Output = bool>,
+    {
+        let mut attempts = Vec::new();
+        let mut last_error = "No attempts were made".to_string();
+
+        while attempts.len() < options.max_attempts as usize {
+            if let Some(max) = options.max_total_cost {
+                let spent: f64 = attempts.iter().filter_map(|a: &RetryAttempt| a.cost).sum();
+                let average = spent / attempts.len().max(1) as f64;
+
+                if spent + average > max {
+                    last_error = format!("Cost limit of {} reached after spending {}", max, spent);
+                    break;
+                }
+            }
+
+            let pending = match self.submit(task).await {
+                Ok(o) => o,
+                Err(e) => {
+                    attempts.push(RetryAttempt {
+                        task_id: None,
+                        cost: None,
+                        outcome: AttemptOutcome::Failed(e.clone()),
+                    });
+
+                    if !is_retryable(&e) {
+                        return RetryOutcome {
+                            result: Err(e),
+                            attempts,
+                        };
+                    }
+
+                    self.back_off(&e, attempts.len() < options.max_attempts as usize)
+                        .await;
+                    last_error = e;
+                    continue;
+                }
+            };
+
+            let task_id = pending.task_id().clone();
+
+            match pending.wait_detailed(self).await {
+                Ok(res) => {
+                    let cost = res.meta.cost;
+
+                    if (options.verifier)(&res.solution).await {
+                        attempts.push(RetryAttempt {
+                            task_id: Some(task_id.clone()),
+                            cost,
+                            outcome: AttemptOutcome::Solved,
+                        });
+
+                        return RetryOutcome {
+                            result: Ok(Solution {
+                                task_id,
+                                solution: res.solution,
+                            }),
+                            attempts,
+                        };
+                    }
+
+                    let feedback = FeedbackResult {
+                        invalid: true,
+                        code: None,
+                        message: Some("Rejected by verifier".to_string()),
+                    };
+
+                    // A failed report shouldn't stop us from trying again
+                    let _ = self.feedback(&task_id, feedback).await;
+
+                    attempts.push(RetryAttempt {
+                        task_id: Some(task_id),
+                        cost,
+                        outcome: AttemptOutcome::Rejected,
+                    });
+                    last_error = "Solution rejected by verifier".to_string();
+                }
+                Err(e) => {
+                    attempts.push(RetryAttempt {
+                        task_id: Some(task_id),
+                        cost: None,
+                        outcome: AttemptOutcome::Failed(e.clone()),
+                    });
+
+                    if !is_retryable(&e) {
+                        return RetryOutcome {
+                            result: Err(e),
+                            attempts,
+                        };
+                    }
+
+                    self.back_off(&e, attempts.len() < options.max_attempts as usize)
+                        .await;
+                    last_error = e;
+                }
+            }
+        }
+
+        RetryOutcome {
+            result: Err(last_error),
+            attempts,
+        }
+    }
+}
+
+impl CapSolver {
+    /// Waits before the next attempt if `err` says CapSolver is overloaded.
+    async fn back_off(&self, err: &str, retrying: bool) {
+        let transient =
+            ApiErrorCode::from_error(err).is_some_and(|c| c.category() == ErrorCategory::Transient);
+
+        if transient && retrying {
+            sleep(Duration::from_millis(self.config.interval)).await;
+        }
+    }
+}
+
+fn is_retryable(err: &str) -> bool {
+    ApiErrorCode::from_error(err).is_some_and(|c| c.category().is_retryable())
+}
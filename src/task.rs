@@ -0,0 +1,657 @@
+use crate::{
+    AwsWafToken, AwsWafType, CachePolicy, CloudflareChallengeToken, CloudflareTurnstileToken,
+    GeeTestType, GeeTestV3Token, GeeTestV4Token, HCaptchaEnterprisePayload, HCaptchaToken,
+    HCaptchaType, Proxy, ReCaptchaEnterprisePayload, ReCaptchaToken, ReCaptchaV2Type,
+    ReCaptchaV3Type, TaskCookie, TaskType, TurnstileType,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime};
+
+/// A task that knows its `createTask` payload and the solution it resolves to.
+///
+/// Implement it for task types the crate doesn't cover yet, they can then be
+/// passed to [`CapSolver::solve`](crate::CapSolver::solve) like any built-in task:
+///
+/// ```no_run
+/// use capsolver::CapSolverTask;
+/// use serde::Deserialize;
+/// use serde_json::{json, Value};
+///
+/// struct NewCaptchaTask {
+///     website_url: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct NewCaptchaSolution {
+///     token: String,
+/// }
+///
+/// impl CapSolverTask for NewCaptchaTask {
+///     type Solution = NewCaptchaSolution;
+///
+///     fn task(&self) -> Result<Value, String> {
+///         Ok(json!({
+///             "type": "NewCaptchaTaskProxyLess",
+///             "websiteURL": self.website_url
+///         }))
+///     }
+/// }
+/// ```
+pub trait CapSolverTask {
+    type Solution: DeserializeOwned;
+
+    /// The `task` object sent to `createTask`.
+    fn task(&self) -> Result<Value, String>;
+
+    /// Checks the task before anything is sent to CapSolver.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether `solution` may be served from a [`SolutionCache`](crate::SolutionCache)
+    /// and until when, `None` if it can't be cached.
+    fn cache_policy(&self, _solution: &Self::Solution) -> Option<CachePolicy> {
+        None
+    }
+}
+
+fn require(name: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err(format!("{} is required", name));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct GeeTestV3Task {
+    pub r#type: Option<GeeTestType>,
+    pub website_url: String,
+    pub gt: String,
+    pub challenge: String,
+    pub api_server_subdomain: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl GeeTestV3Task {
+    pub fn new(website_url: &str, gt: &str, challenge: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            gt: gt.to_string(),
+            challenge: challenge.to_string(),
+            api_server_subdomain: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for GeeTestV3Task {
+    type Solution = GeeTestV3Token;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": GeeTestType::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "gt": self.gt,
+            "challenge": self.challenge
+        });
+
+        if let Some(subdomain) = &self.api_server_subdomain {
+            task["geetestApiServerSubdomain"] = json!(subdomain);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("gt", &self.gt)?;
+        require("challenge", &self.challenge)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeeTestV4Task {
+    pub r#type: Option<GeeTestType>,
+    pub website_url: String,
+    pub captcha_id: String,
+    pub api_server_subdomain: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl GeeTestV4Task {
+    pub fn new(website_url: &str, captcha_id: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            captcha_id: captcha_id.to_string(),
+            api_server_subdomain: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for GeeTestV4Task {
+    type Solution = GeeTestV4Token;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": GeeTestType::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "captchaId": self.captcha_id
+        });
+
+        if let Some(subdomain) = &self.api_server_subdomain {
+            task["geetestApiServerSubdomain"] = json!(subdomain);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("captchaId", &self.captcha_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AwsWafTask {
+    pub r#type: Option<AwsWafType>,
+    pub website_url: String,
+    pub aws_key: Option<String>,
+    pub aws_iv: Option<String>,
+    pub aws_context: Option<String>,
+    pub aws_challenge_js: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl AwsWafTask {
+    pub fn new(website_url: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            aws_key: None,
+            aws_iv: None,
+            aws_context: None,
+            aws_challenge_js: None,
+            proxy: None,
+        }
+    }
+
+    /// Builds the task from the challenge page returned by `website_url`,
+    /// picking up `window.gokuProps` and the `challenge.js` script if present.
+    pub fn from_html(website_url: &str, html: &str) -> Self {
+        let mut task = Self::new(website_url);
+
+        if let Some(props) = goku_props(html) {
+            let field = |name: &str| props[name].as_str().map(str::to_string);
+
+            task.aws_key = field("key");
+            task.aws_iv = field("iv");
+            task.aws_context = field("context");
+        }
+
+        task.aws_challenge_js = challenge_js(html);
+        task
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+fn goku_props(html: &str) -> Option<Value> {
+    let start = html.find("gokuProps")?;
+    let html = &html[start..];
+    let open = html.find('{')?;
+    let close = open + html[open..].find('}')?;
+
+    serde_json::from_str(&html[open..=close]).ok()
+}
+
+fn challenge_js(html: &str) -> Option<String> {
+    let end = html.find("challenge.js")? + "challenge.js".len();
+    let start = html[..end].rfind(['"', '\''])? + 1;
+
+    Some(html[start..end].to_string())
+}
+
+impl CapSolverTask for AwsWafTask {
+    type Solution = AwsWafToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": AwsWafType::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url
+        });
+
+        if let Some(aws_key) = &self.aws_key {
+            task["awsKey"] = json!(aws_key);
+        }
+
+        if let Some(aws_iv) = &self.aws_iv {
+            task["awsIv"] = json!(aws_iv);
+        }
+
+        if let Some(aws_context) = &self.aws_context {
+            task["awsContext"] = json!(aws_context);
+        }
+
+        if let Some(aws_challenge_js) = &self.aws_challenge_js {
+            task["awsChallengeJS"] = json!(aws_challenge_js);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TurnstileMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cdata: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TurnstileTask {
+    pub r#type: Option<TurnstileType>,
+    pub website_url: String,
+    pub website_key: String,
+    pub metadata: Option<TurnstileMetadata>,
+    pub proxy: Option<Proxy>,
+}
+
+impl TurnstileTask {
+    pub fn new(website_url: &str, website_key: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            website_key: website_key.to_string(),
+            metadata: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for TurnstileTask {
+    type Solution = CloudflareTurnstileToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": TurnstileType::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "websiteKey": self.website_key
+        });
+
+        if let Some(metadata) = &self.metadata {
+            task["metadata"] = json!(metadata);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("websiteKey", &self.website_key)
+    }
+}
+
+/// Cloudflare's "Just a moment..." challenge page, which always needs a proxy.
+#[derive(Debug, Clone)]
+pub struct CloudflareChallengeTask {
+    pub website_url: String,
+    pub html: Option<String>,
+    pub user_agent: Option<String>,
+    pub proxy: Proxy,
+    /// How long the clearance may be served from a [`SolutionCache`](crate::SolutionCache)
+    /// to other solves of this task, `None` to never cache it. It's bound to the
+    /// proxy and user agent, which are part of the task.
+    pub reuse_for: Option<Duration>,
+}
+
+impl CloudflareChallengeTask {
+    pub fn new(website_url: &str, proxy: Proxy) -> Self {
+        Self {
+            website_url: website_url.to_string(),
+            html: None,
+            user_agent: None,
+            proxy,
+            reuse_for: None,
+        }
+    }
+}
+
+impl CapSolverTask for CloudflareChallengeTask {
+    type Solution = CloudflareChallengeToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": "AntiCloudflareTask",
+            "websiteURL": self.website_url,
+            "proxy": self.proxy
+        });
+
+        if let Some(html) = &self.html {
+            task["html"] = json!(html);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            task["userAgent"] = json!(user_agent);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)
+    }
+
+    fn cache_policy(&self, _solution: &CloudflareChallengeToken) -> Option<CachePolicy> {
+        self.reuse_for
+            .map(|d| CachePolicy::reusable(SystemTime::now() + d))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReCaptchaV2Task {
+    pub r#type: Option<ReCaptchaV2Type>,
+    pub website_url: String,
+    pub website_key: String,
+    pub page_action: Option<String>,
+    pub is_invisible: Option<bool>,
+    pub enterprise_payload: Option<ReCaptchaEnterprisePayload>,
+    pub api_domain: Option<String>,
+    pub user_agent: Option<String>,
+    pub cookies: Option<Vec<TaskCookie>>,
+    pub anchor: Option<String>,
+    pub reload: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl ReCaptchaV2Task {
+    pub fn new(website_url: &str, website_key: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            website_key: website_key.to_string(),
+            page_action: None,
+            is_invisible: None,
+            enterprise_payload: None,
+            api_domain: None,
+            user_agent: None,
+            cookies: None,
+            anchor: None,
+            reload: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for ReCaptchaV2Task {
+    type Solution = ReCaptchaToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": ReCaptchaV2Type::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "websiteKey": self.website_key
+        });
+
+        if let Some(page_action) = &self.page_action {
+            task["pageAction"] = json!(page_action);
+        }
+
+        if let Some(is_invisible) = self.is_invisible {
+            task["isInvisible"] = json!(is_invisible);
+        }
+
+        if let Some(enterprise_payload) = &self.enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
+        }
+
+        if let Some(api_domain) = &self.api_domain {
+            task["apiDomain"] = json!(api_domain);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            task["userAgent"] = json!(user_agent);
+        }
+
+        if let Some(cookies) = &self.cookies {
+            task["cookies"] = json!(cookies);
+        }
+
+        if let Some(anchor) = &self.anchor {
+            task["anchor"] = json!(anchor);
+        }
+
+        if let Some(reload) = &self.reload {
+            task["reload"] = json!(reload);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("websiteKey", &self.website_key)
+    }
+
+    fn cache_policy(&self, solution: &ReCaptchaToken) -> Option<CachePolicy> {
+        solution.expires_at().map(CachePolicy::single_use)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReCaptchaV3Task {
+    pub r#type: Option<ReCaptchaV3Type>,
+    pub website_url: String,
+    pub website_key: String,
+    pub page_action: String,
+    pub min_score: Option<f64>,
+    pub enterprise_payload: Option<ReCaptchaEnterprisePayload>,
+    pub api_domain: Option<String>,
+    pub user_agent: Option<String>,
+    pub cookies: Option<Vec<TaskCookie>>,
+    pub anchor: Option<String>,
+    pub reload: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl ReCaptchaV3Task {
+    pub fn new(website_url: &str, website_key: &str, page_action: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            website_key: website_key.to_string(),
+            page_action: page_action.to_string(),
+            min_score: None,
+            enterprise_payload: None,
+            api_domain: None,
+            user_agent: None,
+            cookies: None,
+            anchor: None,
+            reload: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for ReCaptchaV3Task {
+    type Solution = ReCaptchaToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": ReCaptchaV3Type::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "websiteKey": self.website_key,
+            "pageAction": self.page_action
+        });
+
+        if let Some(min_score) = self.min_score {
+            task["minScore"] = json!(min_score);
+        }
+
+        if let Some(enterprise_payload) = &self.enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
+        }
+
+        if let Some(api_domain) = &self.api_domain {
+            task["apiDomain"] = json!(api_domain);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            task["userAgent"] = json!(user_agent);
+        }
+
+        if let Some(cookies) = &self.cookies {
+            task["cookies"] = json!(cookies);
+        }
+
+        if let Some(anchor) = &self.anchor {
+            task["anchor"] = json!(anchor);
+        }
+
+        if let Some(reload) = &self.reload {
+            task["reload"] = json!(reload);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("websiteKey", &self.website_key)?;
+        require("pageAction", &self.page_action)
+    }
+
+    fn cache_policy(&self, solution: &ReCaptchaToken) -> Option<CachePolicy> {
+        solution.expires_at().map(CachePolicy::single_use)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HCaptchaTask {
+    pub r#type: Option<HCaptchaType>,
+    pub website_url: String,
+    pub website_key: String,
+    pub is_invisible: Option<bool>,
+    pub enterprise_payload: Option<HCaptchaEnterprisePayload>,
+    pub user_agent: Option<String>,
+    pub proxy: Option<Proxy>,
+}
+
+impl HCaptchaTask {
+    pub fn new(website_url: &str, website_key: &str) -> Self {
+        Self {
+            r#type: None,
+            website_url: website_url.to_string(),
+            website_key: website_key.to_string(),
+            is_invisible: None,
+            enterprise_payload: None,
+            user_agent: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+impl CapSolverTask for HCaptchaTask {
+    type Solution = HCaptchaToken;
+
+    fn task(&self) -> Result<Value, String> {
+        let mut task = json!({
+            "type": HCaptchaType::resolve(self.r#type, self.proxy.is_some())?,
+            "websiteURL": self.website_url,
+            "websiteKey": self.website_key
+        });
+
+        if let Some(is_invisible) = self.is_invisible {
+            task["isInvisible"] = json!(is_invisible);
+        }
+
+        if let Some(enterprise_payload) = &self.enterprise_payload {
+            task["enterprisePayload"] = json!(enterprise_payload);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            task["userAgent"] = json!(user_agent);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            task["proxy"] = json!(proxy);
+        }
+
+        Ok(task)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        require("websiteURL", &self.website_url)?;
+        require("websiteKey", &self.website_key)
+    }
+
+    fn cache_policy(&self, solution: &HCaptchaToken) -> Option<CachePolicy> {
+        solution.expires_at().map(CachePolicy::single_use)
+    }
+}
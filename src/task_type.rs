@@ -0,0 +1,146 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// A family of task types that only differ in whether they take a proxy.
+pub trait TaskType: Copy + FromStr<Err = String> + fmt::Display {
+    /// The type used when a proxy is supplied and no type is forced.
+    fn with_proxy() -> Self;
+
+    /// The type used when no proxy is supplied and no type is forced.
+    fn proxyless() -> Self;
+
+    fn uses_proxy(&self) -> bool;
+
+    fn as_str(&self) -> &'static str;
+
+    /// Picks the type to send, checking a forced type against the proxy.
+    fn resolve(forced: Option<Self>, has_proxy: bool) -> Result<Self, String> {
+        match forced {
+            Some(t) if t.uses_proxy() && !has_proxy => Err(format!("{} requires a proxy", t)),
+            Some(t) if !t.uses_proxy() && has_proxy => Err(format!("{} does not take a proxy", t)),
+            Some(t) => Ok(t),
+            None if has_proxy => Ok(Self::with_proxy()),
+            None => Ok(Self::proxyless()),
+        }
+    }
+}
+
+macro_rules! task_type {
+    ($name:ident { proxy: $proxy:ident, proxyless: $proxyless:ident $(, $other:ident)* $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $proxy,
+            $proxyless,
+            $($other,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [Self] = &[Self::$proxy, Self::$proxyless, $(Self::$other,)*];
+        }
+
+        impl TaskType for $name {
+            fn with_proxy() -> Self {
+                Self::$proxy
+            }
+
+            fn proxyless() -> Self {
+                Self::$proxyless
+            }
+
+            fn uses_proxy(&self) -> bool {
+                // CapSolver names every type that runs without a proxy `...ProxyLess`
+                !self.as_str().ends_with("ProxyLess")
+            }
+
+            fn as_str(&self) -> &'static str {
+                match self {
+                    Self::$proxy => stringify!($proxy),
+                    Self::$proxyless => stringify!($proxyless),
+                    $(Self::$other => stringify!($other),)*
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::ALL
+                    .iter()
+                    .find(|t| t.as_str() == s)
+                    .copied()
+                    .ok_or_else(|| format!("Unsupported type: {}", s))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?
+                    .parse()
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+task_type!(HCaptchaType {
+    proxy: HCaptchaTask,
+    proxyless: HCaptchaTaskProxyLess,
+    HCaptchaTurboTask,
+});
+
+task_type!(FunCaptchaType {
+    proxy: FunCaptchaTask,
+    proxyless: FunCaptchaTaskProxyLess,
+});
+
+task_type!(GeeTestType {
+    proxy: GeeTestTask,
+    proxyless: GeeTestTaskProxyLess,
+});
+
+task_type!(ReCaptchaV2Type {
+    proxy: ReCaptchaV2Task,
+    proxyless: ReCaptchaV2TaskProxyLess,
+    ReCaptchaV2EnterpriseTask,
+    ReCaptchaV2EnterpriseTaskProxyLess,
+});
+
+task_type!(ReCaptchaV3Type {
+    proxy: ReCaptchaV3Task,
+    proxyless: ReCaptchaV3TaskProxyLess,
+    ReCaptchaV3EnterpriseTask,
+    ReCaptchaV3EnterpriseTaskProxyLess,
+});
+
+task_type!(MtCaptchaType {
+    proxy: MtCaptchaTask,
+    proxyless: MtCaptchaTaskProxyLess,
+});
+
+task_type!(AwsWafType {
+    proxy: AwsWafTask,
+    proxyless: AwsWafTaskProxyLess,
+});
+
+task_type!(CyberSiAraType {
+    proxy: AntiCyberSiAraTask,
+    proxyless: AntiCyberSiAraTaskProxyLess,
+});
+
+task_type!(TurnstileType {
+    proxy: AntiTurnstileTask,
+    proxyless: AntiTurnstileTaskProxyLess,
+});